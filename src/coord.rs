@@ -8,8 +8,11 @@ impl Coord {
         Coord(x, y, z)
     }
 
-    pub fn manhattan_distance(&self, b: &Self) -> u64 {
-        ((self.x() - b.x()).abs() + (self.y() - b.y()).abs() + (self.z() - b.z()).abs()) as u64
+    // Wide enough for any two points, however far apart.
+    pub fn manhattan_distance(&self, b: &Self) -> u128 {
+        u128::from(self.x().abs_diff(b.x()))
+            + u128::from(self.y().abs_diff(b.y()))
+            + u128::from(self.z().abs_diff(b.z()))
     }
 
     pub fn x(&self) -> i64 {
@@ -1,6 +1,6 @@
 use regex::Regex;
 use std::cmp;
-use std::collections::HashSet;
+use summed_area::SummedAreaTable;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Claim {
@@ -64,65 +64,50 @@ pub fn input_generator(input: &str) -> Vec<Claim> {
         }).collect()
 }
 
-fn intersections(claims: &[Claim]) -> HashSet<(u32, u32)> {
-    let mut squares = HashSet::new();
-
-    pairs(claims)
-        .iter()
-        .filter_map(|(a, b)| {
-            if a.rect == b.rect {
-                return None;
-            }
-
-            a.rect.intersection(&b.rect)
-        }).for_each(|intersection| {
-            for x in intersection.left..=intersection.right {
-                for y in intersection.top..=intersection.bottom {
-                    squares.insert((x, y));
-                }
-            }
-        });
+// Whether each square inch is claimed more than once, as a table so that any
+// rectangle's overlap can be counted directly. Claims are added to a grid of
+// differences, whose prefix sums are the number of claims on each square.
+fn overlaps(claims: &[Claim]) -> SummedAreaTable {
+    let width = claims.iter().map(|c| c.rect.right as usize + 2).max().unwrap_or(0);
+    let height = claims.iter().map(|c| c.rect.bottom as usize + 2).max().unwrap_or(0);
+
+    let mut differences = vec![0; width * height];
+    for c in claims {
+        let (left, top) = (c.rect.left as usize, c.rect.top as usize);
+        let (right, bottom) = (c.rect.right as usize + 1, c.rect.bottom as usize + 1);
+        differences[top * width + left] += 1;
+        differences[top * width + right] -= 1;
+        differences[bottom * width + left] -= 1;
+        differences[bottom * width + right] += 1;
+    }
 
-    squares
+    let claimed = SummedAreaTable::new(width, height, |x, y| differences[y * width + x]);
+    SummedAreaTable::new(width, height, |x, y| {
+        if claimed.sum(0, 0, x + 1, y + 1) > 1 {
+            1
+        } else {
+            0
+        }
+    })
 }
 
 #[aoc(day3, part1)]
 fn answer_1(claims: &[Claim]) -> usize {
-    intersections(claims).len()
+    let overlaps = overlaps(claims);
+    overlaps.sum(0, 0, overlaps.width(), overlaps.height()) as usize
 }
 
 #[aoc(day3, part2)]
 fn answer_2(claims: &[Claim]) -> u32 {
-    let squares = intersections(claims);
+    let overlaps = overlaps(claims);
 
-    let ids = claims
+    claims
         .iter()
-        .filter(|c| {
-            for x in c.rect.left..=c.rect.right {
-                for y in c.rect.top..=c.rect.bottom {
-                    if squares.contains(&(x, y)) {
-                        return false;
-                    }
-                }
-            }
-
-            true
+        .find(|c| {
+            let (x, y) = (c.rect.left as usize, c.rect.top as usize);
+            overlaps.sum(x, y, c.rect.width() as usize, c.rect.height() as usize) == 0
         }).map(|c| c.id)
-        .collect::<Vec<u32>>();
-
-    ids[0]
-}
-
-fn pairs<'a>(xs: &'a [Claim]) -> Vec<(&'a Claim, &'a Claim)> {
-    let mut v = Vec::new();
-
-    for a in xs.iter() {
-        for b in xs.into_iter() {
-            v.push((a, b))
-        }
-    }
-
-    v
+        .unwrap()
 }
 
 #[cfg(test)]
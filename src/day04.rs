@@ -0,0 +1,361 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+
+// Fields in order of significance, so the derived ordering is chronological
+// across day, month and year boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    BeginsShift(u32),
+    FallsAsleep,
+    WakesUp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub time: Timestamp,
+    pub event: Event,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogError {
+    Parse { line: usize, text: String },
+    NoGuard(Timestamp),
+    AlreadyAsleep(Timestamp),
+    NotAsleep(Timestamp),
+    OutsideMidnight(Timestamp),
+    StillAsleep(Timestamp),
+}
+
+impl std::fmt::Display for LogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LogError::Parse { line, text } => {
+                write!(f, "invalid record on line {}: {:?}", line, text)
+            }
+            LogError::NoGuard(t) => write!(f, "{}: event before any guard's shift", t),
+            LogError::AlreadyAsleep(t) => write!(f, "{}: guard falls asleep twice", t),
+            LogError::NotAsleep(t) => write!(f, "{}: guard wakes up without sleeping", t),
+            LogError::OutsideMidnight(t) => write!(f, "{}: guard sleeps outside midnight", t),
+            LogError::StillAsleep(t) => write!(f, "{}: guard never wakes up", t),
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
+
+// Parses every record, sorted by time. Records with the same time keep their
+// order in the input.
+pub fn parse_log(input: &str) -> Result<Vec<Record>, LogError> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(
+            r"^\[(\d{4})-(\d{2})-(\d{2}) (\d{2}):(\d{2})\] (?:Guard #(\d+) begins shift|(falls asleep)|(wakes up))$"
+        )
+        .unwrap();
+    }
+
+    let mut records = input
+        .trim()
+        .lines()
+        .enumerate()
+        .map(|(i, l)| {
+            let error = || LogError::Parse {
+                line: i + 1,
+                text: l.to_string(),
+            };
+            let c = RE.captures(l.trim()).ok_or_else(error)?;
+            let number = |n: usize| c[n].parse().map_err(|_| error());
+
+            let time = Timestamp {
+                year: number(1)?,
+                month: number(2)?,
+                day: number(3)?,
+                hour: number(4)?,
+                minute: number(5)?,
+            };
+            if time.month < 1 || time.month > 12 || time.day < 1 || time.day > 31 {
+                return Err(error());
+            }
+            if time.hour > 23 || time.minute > 59 {
+                return Err(error());
+            }
+
+            let event = if c.get(6).is_some() {
+                Event::BeginsShift(number(6)?)
+            } else if c.get(7).is_some() {
+                Event::FallsAsleep
+            } else {
+                Event::WakesUp
+            };
+
+            Ok(Record { time, event })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    records.sort_by_key(|r| r.time);
+    Ok(records)
+}
+
+// How many times a guard was asleep during each minute past midnight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram([u32; 60]);
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram([0; 60])
+    }
+}
+
+impl Histogram {
+    pub fn minutes(&self) -> &[u32; 60] {
+        &self.0
+    }
+
+    pub fn total(&self) -> u32 {
+        self.0.iter().sum()
+    }
+
+    // The minute most often slept through and how often, the earliest among
+    // equals.
+    pub fn sleepiest_minute(&self) -> (u32, u32) {
+        self.0
+            .iter()
+            .enumerate()
+            .fold((0, 0), |(best, most), (minute, &count)| {
+                if count > most {
+                    (minute as u32, count)
+                } else {
+                    (best, most)
+                }
+            })
+    }
+}
+
+// Replays sorted records into a histogram for each guard on duty.
+pub fn sleep_histograms(records: &[Record]) -> Result<BTreeMap<u32, Histogram>, LogError> {
+    let mut histograms: BTreeMap<u32, Histogram> = BTreeMap::new();
+    let mut guard = None;
+    let mut asleep: Option<Timestamp> = None;
+
+    for record in records {
+        match record.event {
+            Event::BeginsShift(id) => {
+                if let Some(t) = asleep {
+                    return Err(LogError::StillAsleep(t));
+                }
+                guard = Some(id);
+                histograms.entry(id).or_default();
+            }
+            Event::FallsAsleep => {
+                if guard.is_none() {
+                    return Err(LogError::NoGuard(record.time));
+                }
+                if asleep.is_some() {
+                    return Err(LogError::AlreadyAsleep(record.time));
+                }
+                if record.time.hour != 0 {
+                    return Err(LogError::OutsideMidnight(record.time));
+                }
+                asleep = Some(record.time);
+            }
+            Event::WakesUp => {
+                let id = guard.ok_or(LogError::NoGuard(record.time))?;
+                let start = asleep.take().ok_or(LogError::NotAsleep(record.time))?;
+                let woken_same_hour = record.time.hour == 0
+                    && (record.time.year, record.time.month, record.time.day)
+                        == (start.year, start.month, start.day);
+                if !woken_same_hour {
+                    return Err(LogError::OutsideMidnight(record.time));
+                }
+
+                let histogram = histograms.entry(id).or_default();
+                for minute in start.minute..record.time.minute {
+                    histogram.0[minute as usize] += 1;
+                }
+            }
+        }
+    }
+
+    match asleep {
+        Some(t) => Err(LogError::StillAsleep(t)),
+        None => Ok(histograms),
+    }
+}
+
+#[aoc_generator(day4)]
+pub fn input_generator(input: &str) -> Result<BTreeMap<u32, Histogram>, LogError> {
+    sleep_histograms(&parse_log(input)?)
+}
+
+// The guard asleep the most, times the minute they are most often asleep.
+#[aoc(day4, part1)]
+fn answer_1(histograms: &BTreeMap<u32, Histogram>) -> u32 {
+    histograms
+        .iter()
+        .max_by_key(|(_, h)| h.total())
+        .map_or(0, |(id, h)| id * h.sleepiest_minute().0)
+}
+
+// The guard most often asleep on any one minute, times that minute.
+#[aoc(day4, part2)]
+fn answer_2(histograms: &BTreeMap<u32, Histogram>) -> u32 {
+    histograms
+        .iter()
+        .map(|(id, h)| (id, h.sleepiest_minute()))
+        .max_by_key(|&(_, (_, count))| count)
+        .map_or(0, |(id, (minute, _))| id * minute)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_INPUT: &'static str = "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:55] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-03 00:29] wakes up
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+[1518-11-04 00:46] wakes up
+[1518-11-05 00:03] Guard #99 begins shift
+[1518-11-05 00:45] falls asleep
+[1518-11-05 00:55] wakes up";
+
+    fn shuffled(input: &str) -> String {
+        let mut lines: Vec<&str> = input.lines().collect();
+        lines.reverse();
+        let (a, b) = lines.split_at(7);
+        b.iter()
+            .chain(a.iter())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn parses_records() {
+        assert_eq!(
+            vec![
+                Record {
+                    time: Timestamp {
+                        year: 1518,
+                        month: 11,
+                        day: 1,
+                        hour: 0,
+                        minute: 0,
+                    },
+                    event: Event::BeginsShift(10),
+                },
+                Record {
+                    time: Timestamp {
+                        year: 1518,
+                        month: 11,
+                        day: 1,
+                        hour: 0,
+                        minute: 5,
+                    },
+                    event: Event::FallsAsleep,
+                },
+            ],
+            parse_log(&TEST_INPUT.lines().take(2).collect::<Vec<_>>().join("\n")).unwrap()
+        );
+        assert_eq!(parse_log(TEST_INPUT), parse_log(&shuffled(TEST_INPUT)));
+    }
+
+    #[test]
+    fn sorts_across_month_boundaries() {
+        let records = parse_log(
+            "\
+[1518-04-01 00:10] wakes up
+[1518-03-31 23:59] Guard #7 begins shift
+[1518-04-01 00:00] falls asleep",
+        )
+        .unwrap();
+
+        assert_eq!(Event::BeginsShift(7), records[0].event);
+        assert_eq!("1518-03-31 23:59", records[0].time.to_string());
+        assert_eq!(10, sleep_histograms(&records).unwrap()[&7].total());
+    }
+
+    #[test]
+    fn histograms() {
+        let histograms = input_generator(TEST_INPUT).unwrap();
+
+        assert_eq!(50, histograms[&10].total());
+        assert_eq!((24, 2), histograms[&10].sleepiest_minute());
+        assert_eq!((45, 3), histograms[&99].sleepiest_minute());
+    }
+
+    #[test]
+    fn invalid_logs() {
+        assert_eq!(
+            Err(LogError::Parse {
+                line: 2,
+                text: "[1518-11-01 00:05] dozes off".to_string()
+            }),
+            parse_log("[1518-11-01 00:00] Guard #10 begins shift\n[1518-11-01 00:05] dozes off")
+        );
+        assert_eq!(
+            Err(LogError::NoGuard(Timestamp {
+                year: 1518,
+                month: 11,
+                day: 1,
+                hour: 0,
+                minute: 5,
+            })),
+            input_generator("[1518-11-01 00:05] falls asleep")
+        );
+        assert!(matches!(
+            input_generator(
+                "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] wakes up"
+            ),
+            Err(LogError::NotAsleep(_))
+        ));
+        assert!(matches!(
+            input_generator(
+                "\
+[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep"
+            ),
+            Err(LogError::StillAsleep(_))
+        ));
+    }
+
+    #[test]
+    fn examples_1() {
+        assert_eq!(240, answer_1(&input_generator(TEST_INPUT).unwrap()));
+    }
+
+    #[test]
+    fn examples_2() {
+        assert_eq!(4455, answer_2(&input_generator(TEST_INPUT).unwrap()));
+    }
+}
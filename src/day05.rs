@@ -0,0 +1,93 @@
+use rayon::prelude::*;
+
+// Units react when they are the same type with opposite polarities.
+fn reacts(a: u8, b: u8) -> bool {
+    a != b && a.eq_ignore_ascii_case(&b)
+}
+
+// A single pass keeping the units that have not reacted yet on a stack.
+// Each new unit either reacts with the top of the stack or is pushed.
+fn reduce<'a, I>(units: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = &'a u8>,
+{
+    let mut stack = Vec::new();
+    for &unit in units {
+        match stack.last() {
+            Some(&top) if reacts(top, unit) => {
+                stack.pop();
+            }
+            _ => stack.push(unit),
+        }
+    }
+
+    stack
+}
+
+// The polymer left once every reaction has happened.
+pub fn react(polymer: &[u8]) -> Vec<u8> {
+    reduce(polymer)
+}
+
+// `react`, with every unit of the given type removed first.
+pub fn react_without(polymer: &[u8], unit: u8) -> Vec<u8> {
+    reduce(polymer.iter().filter(|u| !u.eq_ignore_ascii_case(&unit)))
+}
+
+#[aoc_generator(day5)]
+pub fn input_generator(input: &str) -> Vec<u8> {
+    input.trim().as_bytes().to_vec()
+}
+
+#[aoc(day5, part1)]
+fn answer_1(polymer: &[u8]) -> usize {
+    react(polymer).len()
+}
+
+// Removing a unit type commutes with reacting, so each retry starts from the
+// already reacted polymer.
+#[aoc(day5, part2)]
+fn answer_2(polymer: &[u8]) -> usize {
+    let reacted = react(polymer);
+
+    (b'a'..=b'z')
+        .into_par_iter()
+        .map(|unit| react_without(&reacted, unit).len())
+        .min()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reactions() {
+        assert_eq!(b"".to_vec(), react(b"aA"));
+        assert_eq!(b"".to_vec(), react(b"abBA"));
+        assert_eq!(b"abAB".to_vec(), react(b"abAB"));
+        assert_eq!(b"aabAAB".to_vec(), react(b"aabAAB"));
+        assert_eq!(b"dabCBAcaDA".to_vec(), react(b"dabAcCaCBAcCcaDA"));
+    }
+
+    #[test]
+    fn removals() {
+        assert_eq!(b"dbCBcD".to_vec(), react_without(b"dabAcCaCBAcCcaDA", b'a'));
+        assert_eq!(
+            b"daCAcaDA".to_vec(),
+            react_without(b"dabAcCaCBAcCcaDA", b'B')
+        );
+        assert_eq!(b"daDA".to_vec(), react_without(b"dabAcCaCBAcCcaDA", b'c'));
+        assert_eq!(b"abCBAc".to_vec(), react_without(b"dabAcCaCBAcCcaDA", b'd'));
+    }
+
+    #[test]
+    fn examples_1() {
+        assert_eq!(10, answer_1(&input_generator("dabAcCaCBAcCcaDA\n")));
+    }
+
+    #[test]
+    fn examples_2() {
+        assert_eq!(4, answer_2(&input_generator("dabAcCaCBAcCcaDA\n")));
+    }
+}
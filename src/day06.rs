@@ -1,12 +1,11 @@
+use coord::Coord;
+use manhattan::Manhattan;
 use point::Point;
-use std::collections::HashMap;
 
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 static MAX_PROXIMITY: AtomicUsize = AtomicUsize::new(10000);
 
-type Grid<'a> = HashMap<Point, (&'a Point, u64)>;
-
 #[aoc_generator(day6)]
 pub fn input_generator(input: &str) -> Vec<Point> {
     input
@@ -20,112 +19,97 @@ pub fn input_generator(input: &str) -> Vec<Point> {
         }).collect()
 }
 
-fn bounds(points: &[Point]) -> (Point, Point) {
-    let min = Point::new(0, 0);
-    let max = points.iter().fold(min.clone(), |m, p| {
-        Point::new(
-            if p.x() > m.x() { p.x() } else { m.x() },
-            if p.y() > m.y() { p.y() } else { m.y() },
-        )
-    });
+pub fn coord_generator(input: &str) -> Vec<Coord> {
+    input
+        .lines()
+        .map(|l| {
+            let mut parts = l.split(", ");
+            let x = parts.next().unwrap().parse().unwrap();
+            let y = parts.next().unwrap().parse().unwrap();
+            let z = parts.next().unwrap().parse().unwrap();
 
-    (min, max)
+            Coord::new(x, y, z)
+        }).collect()
 }
 
-#[aoc(day6, part1)]
-fn answer_1(input: &[Point]) -> usize {
-    let boundary = bounds(input);
-    let grid = populate_grid(input, &boundary);
-
-    input
+// The bounding box always includes the origin so that 2D inputs keep the
+// `(0, 0)` corner they have always been measured from.
+fn bounds<P: Manhattan>(sites: &[P]) -> (P, P) {
+    sites
         .iter()
-        .filter(|p| is_finite(&grid, &boundary, p))
-        .map(|p| grid.iter().filter(|(_, (x, _))| p == *x).count())
-        .max()
-        .unwrap()
+        .fold((P::default(), P::default()), |(min, max), p| {
+            (min.min(p), max.max(p))
+        })
 }
 
-#[aoc(day6, part2)]
-fn answer_2(input: &[Point]) -> usize {
-    let boundary = bounds(input);
-    let mut area: usize = 0;
-    let max_proximity = MAX_PROXIMITY.load(Ordering::SeqCst);
-
-    for x in 0..=boundary.1.x() {
-        for y in 0..=boundary.1.y() {
-            let sum = input
-                .iter()
-                .map(|i| i.manhattan_distance(&Point::new(x, y)))
-                .sum::<u64>() as usize;
-            if sum < max_proximity {
-                area += 1
+// Size (area in 2D, volume in 3D) of the largest region closest to a single
+// site that doesn't extend to infinity. A region reaching the bounding box
+// carries on past it, so one pass over the box both sizes the regions and
+// finds the infinite ones.
+pub fn largest_finite_region<P: Manhattan>(sites: &[P]) -> Option<usize> {
+    let (min, max) = bounds(sites);
+    let mut sizes = vec![0; sites.len()];
+    let mut infinite = vec![false; sites.len()];
+
+    for c in P::region(&min, &max) {
+        if let Some(owner) = closest(sites, &c) {
+            sizes[owner] += 1;
+            if c.on_boundary(&min, &max) {
+                infinite[owner] = true;
             }
         }
     }
 
-    area
+    (0..sites.len())
+        .filter(|&i| !infinite[i])
+        .map(|i| sizes[i])
+        .max()
 }
 
-fn populate_grid<'a>(points: &'a [Point], boundary: &(Point, Point)) -> Grid<'a> {
-    let mut grid: Grid = HashMap::with_capacity(boundary.1.x() as usize * boundary.1.y() as usize);
-    for x in 0..=boundary.1.x() {
-        for y in 0..=boundary.1.y() {
-            let c = Point::new(x, y);
-            let mut closest = points
-                .iter()
-                .map(|p| (p, c.manhattan_distance(p)))
-                .collect::<Vec<(&Point, u64)>>();
-            closest.sort_by(|(_, a), (_, b)| a.cmp(b));
-
-            if closest[0].1 != closest[1].1 {
-                grid.insert(c, closest[0]);
-            }
-        }
-    }
+// Size of the region whose total distance to all sites is below
+// `max_proximity`.
+pub fn safe_region_size<P: Manhattan>(sites: &[P], max_proximity: usize) -> usize {
+    let (min, max) = bounds(sites);
 
-    grid
+    P::region(&min, &max)
+        .filter(|c| {
+            let sum = sites.iter().map(|s| s.manhattan_distance(c)).sum::<u128>() as usize;
+            sum < max_proximity
+        }).count()
 }
 
-fn is_finite(grid: &Grid, boundary: &(Point, Point), p: &Point) -> bool {
-    if in_points(grid, &p, (0..=boundary.1.x()).map(|x| Point::new(x, 0))) {
-        return false;
-    }
-    if in_points(
-        &grid,
-        &p,
-        (0..=boundary.1.x()).map(|x| Point::new(x, boundary.1.y())),
-    ) {
-        return false;
-    }
-    if in_points(grid, &p, (0..=boundary.1.y()).map(|y| Point::new(0, y))) {
-        return false;
-    }
-    !in_points(
-        grid,
-        &p,
-        (0..=boundary.1.y()).map(|y| Point::new(boundary.1.x(), y)),
-    )
+#[aoc(day6, part1)]
+fn answer_1(input: &[Point]) -> usize {
+    largest_finite_region(input).unwrap()
+}
+
+#[aoc(day6, part2)]
+fn answer_2(input: &[Point]) -> usize {
+    safe_region_size(input, MAX_PROXIMITY.load(Ordering::SeqCst))
 }
 
-fn in_points<I>(grid: &Grid, p: &Point, points: I) -> bool
-where
-    I: IntoIterator<Item = Point>,
-{
-    for x in points.into_iter() {
-        if let Some(g) = grid.get(&x) {
-            if g.0 == p {
-                return true;
+// The index of the site strictly closest to `c`, if there is one.
+fn closest<P: Manhattan>(sites: &[P], c: &P) -> Option<usize> {
+    let mut best: Option<(usize, u128)> = None;
+    let mut tied = false;
+    for (i, d) in sites.iter().map(|p| c.manhattan_distance(p)).enumerate() {
+        match best {
+            Some((_, b)) if d > b => {}
+            Some((_, b)) if d == b => tied = true,
+            _ => {
+                best = Some((i, d));
+                tied = false;
             }
         }
     }
 
-    false
+    best.filter(|_| !tied).map(|(i, _)| i)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    const TEST_INPUT: &'static str = "\
+    const TEST_INPUT: &str = "\
 1, 1
 1, 6
 8, 3
@@ -133,6 +117,15 @@ mod test {
 5, 5
 8, 9";
 
+    const TEST_INPUT_3D: &str = "\
+0, 3, 3
+6, 3, 3
+3, 0, 3
+3, 6, 3
+3, 3, 0
+3, 3, 6
+3, 3, 3";
+
     #[test]
     fn examples_1() {
         assert_eq!(answer_1(&input_generator(TEST_INPUT)), 17);
@@ -143,4 +136,24 @@ mod test {
         MAX_PROXIMITY.store(32, Ordering::SeqCst);
         assert_eq!(answer_2(&input_generator(TEST_INPUT)), 16);
     }
+
+    #[test]
+    fn flat_coords_match_points() {
+        let coords: Vec<Coord> = input_generator(TEST_INPUT)
+            .iter()
+            .map(|p| Coord::new(p.x(), p.y(), 0))
+            .collect();
+
+        // Every region of a flat layout is unbounded along the z axis
+        assert_eq!(largest_finite_region(&coords), None);
+        assert_eq!(safe_region_size(&coords, 32), 16);
+    }
+
+    #[test]
+    fn volumes_3d() {
+        let coords = coord_generator(TEST_INPUT_3D);
+
+        assert_eq!(largest_finite_region(&coords), Some(27));
+        assert_eq!(safe_region_size(&coords, 24), 7);
+    }
 }
@@ -1,33 +1,171 @@
+use regex::Regex;
+use scheduler::Scheduler;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
-#[derive(Debug, Clone)]
-struct Work(usize, char);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    Parse { line: usize, text: String },
+    SelfDependency(char),
+    DuplicateEdge(char, char),
+    MissingStep { step: char, prerequisite: char },
+    Cycle(Vec<char>),
+}
 
-impl Work {
-    pub fn is_finished(&self, time: usize) -> bool {
-        self.0 + time_required(self.1) < time
+impl std::fmt::Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GraphError::Parse { line, text } => {
+                write!(f, "invalid instruction on line {}: {:?}", line, text)
+            }
+            GraphError::SelfDependency(s) => write!(f, "step {} depends on itself", s),
+            GraphError::DuplicateEdge(p, c) => {
+                write!(f, "duplicate instruction: {} before {}", p, c)
+            }
+            GraphError::MissingStep { step, prerequisite } => {
+                write!(f, "step {} depends on unknown step {}", step, prerequisite)
+            }
+            GraphError::Cycle(steps) => {
+                let steps: Vec<String> = steps.iter().map(|s| s.to_string()).collect();
+                write!(f, "dependency cycle: {}", steps.join(" -> "))
+            }
+        }
     }
 }
 
-#[aoc_generator(day7)]
-pub fn input_generator(input: &str) -> Box<HashMap<char, Vec<char>>> {
-    let i = input.trim().lines().map(|l| {
-        let mut parts = l.split(' ');
+impl std::error::Error for GraphError {}
 
-        (
-            parts.nth(1).unwrap().chars().next().unwrap(),
-            parts.nth(5).unwrap().chars().next().unwrap(),
-        )
-    });
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderError {
+    UnknownStep(char),
+    Repeated(char),
+    Unsatisfied { step: char, prerequisite: char },
+    Missing(char),
+}
+
+impl std::fmt::Display for OrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OrderError::UnknownStep(s) => write!(f, "unknown step {}", s),
+            OrderError::Repeated(s) => write!(f, "step {} appears more than once", s),
+            OrderError::Unsatisfied { step, prerequisite } => {
+                write!(f, "step {} precedes prerequisite {}", step, prerequisite)
+            }
+            OrderError::Missing(s) => write!(f, "step {} is missing", s),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+#[aoc_generator(day7)]
+pub fn input_generator(input: &str) -> Result<Box<HashMap<char, Vec<char>>>, GraphError> {
+    lazy_static! {
+        static ref RE: Regex =
+            Regex::new(r"^Step ([A-Za-z]) must be finished before step ([A-Za-z]) can begin\.$").unwrap();
+    }
 
     let mut edges: HashMap<char, Vec<char>> = HashMap::new();
-    for (p, c) in i {
-        edges.entry(c).or_default().push(p);
+    for (i, l) in input.trim().lines().enumerate() {
+        let c = RE.captures(l.trim()).ok_or_else(|| GraphError::Parse {
+            line: i + 1,
+            text: l.to_string(),
+        })?;
+        let p = c[1].chars().next().unwrap();
+        let s = c[2].chars().next().unwrap();
+
+        if p == s {
+            return Err(GraphError::SelfDependency(s));
+        }
+
+        let prerequisites = edges.entry(s).or_default();
+        if prerequisites.contains(&p) {
+            return Err(GraphError::DuplicateEdge(p, s));
+        }
+        prerequisites.push(p);
         edges.entry(p).or_default();
     }
 
-    Box::new(edges)
+    validate(&edges)?;
+
+    Ok(Box::new(edges))
+}
+
+// Checks that a dependency graph, given as a map of each step to its
+// prerequisites, can be completed.
+pub fn validate(edges: &HashMap<char, Vec<char>>) -> Result<(), GraphError> {
+    let mut steps: Vec<char> = edges.keys().cloned().collect();
+    steps.sort();
+
+    for &s in steps.iter() {
+        for &p in edges[&s].iter() {
+            if p == s {
+                return Err(GraphError::SelfDependency(s));
+            }
+            if !edges.contains_key(&p) {
+                return Err(GraphError::MissingStep {
+                    step: s,
+                    prerequisite: p,
+                });
+            }
+        }
+    }
+
+    let mut state: HashMap<char, Visit> = HashMap::with_capacity(edges.len());
+    let mut path = Vec::new();
+    for &s in steps.iter() {
+        if let Some(cycle) = find_cycle(edges, s, &mut state, &mut path) {
+            return Err(GraphError::Cycle(cycle));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+// Depth-first search along prerequisites. A step reached again while still on
+// the path closes a cycle, which is returned in the order the steps would have
+// to be finished in, starting from its smallest step.
+fn find_cycle(
+    edges: &HashMap<char, Vec<char>>,
+    step: char,
+    state: &mut HashMap<char, Visit>,
+    path: &mut Vec<char>,
+) -> Option<Vec<char>> {
+    match state.get(&step) {
+        Some(Visit::Done) => return None,
+        Some(Visit::InProgress) => {
+            let start = path.iter().position(|&s| s == step).unwrap();
+            let mut cycle = path[start..].to_vec();
+            cycle.reverse();
+
+            let first = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+            cycle.rotate_left(first);
+            return Some(cycle);
+        }
+        None => {}
+    }
+
+    state.insert(step, Visit::InProgress);
+    path.push(step);
+
+    let mut prerequisites = edges[&step].clone();
+    prerequisites.sort();
+    for p in prerequisites {
+        if let Some(cycle) = find_cycle(edges, p, state, path) {
+            return Some(cycle);
+        }
+    }
+
+    path.pop();
+    state.insert(step, Visit::Done);
+
+    None
 }
 
 fn next_steps(edges: &HashMap<char, Vec<char>>, visited: &HashSet<char>) -> Vec<char> {
@@ -61,51 +199,216 @@ fn answer_1(edges: &HashMap<char, Vec<char>>) -> String {
     s
 }
 
-fn time_required(step: char) -> usize {
-    let step_delay = 60;
-    step_delay + (step as u8 - 'A' as u8) as usize
+// Escapes a step name for use inside a double-quoted DOT ID or label.
+fn dot_escape(step: char) -> String {
+    match step {
+        '"' | '\\' => format!("\\{}", step),
+        _ => step.to_string(),
+    }
 }
 
-#[aoc(day7, part2)]
-fn answer_2(edges: &HashMap<char, Vec<char>>) -> usize {
-    let mut total_time = 0;
-    let n_workers = 5;
-    let mut workers: Vec<Work> = Vec::with_capacity(n_workers);
+// Renders the graph in Graphviz DOT format, labelling each step with its
+// position in the order from part 1. Node IDs are quoted so that any step
+// name is a valid ID.
+pub fn to_dot(edges: &HashMap<char, Vec<char>>) -> String {
+    let mut dot = String::from("digraph instructions {\n");
 
-    let mut visited: HashSet<char> = HashSet::new();
+    for (i, step) in answer_1(edges).chars().enumerate() {
+        let step = dot_escape(step);
+        dot.push_str(&format!("    \"{}\" [label=\"{} ({})\"];\n", step, step, i + 1));
+    }
 
-    loop {
-        let mut new_workers = Vec::with_capacity(n_workers);
-        while let Some(w) = workers.pop() {
-            if w.is_finished(total_time) {
-                visited.insert(w.1);
-            } else {
-                new_workers.push(w);
+    let mut pairs: Vec<(char, char)> = edges
+        .iter()
+        .flat_map(|(&s, v)| v.iter().map(move |&p| (p, s)))
+        .collect();
+    pairs.sort();
+    for (p, s) in pairs {
+        dot.push_str(&format!(
+            "    \"{}\" -> \"{}\";\n",
+            dot_escape(p),
+            dot_escape(s)
+        ));
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+// Checks that `order` lists every step exactly once, after all of its
+// prerequisites, reporting the first constraint it breaks.
+pub fn check_order(edges: &HashMap<char, Vec<char>>, order: &str) -> Result<(), OrderError> {
+    let mut visited: HashSet<char> = HashSet::with_capacity(edges.len());
+
+    for step in order.chars() {
+        let prerequisites = edges.get(&step).ok_or(OrderError::UnknownStep(step))?;
+        if visited.contains(&step) {
+            return Err(OrderError::Repeated(step));
+        }
+        if let Some(&p) = prerequisites.iter().filter(|p| !visited.contains(p)).min() {
+            return Err(OrderError::Unsatisfied {
+                step,
+                prerequisite: p,
+            });
+        }
+
+        visited.insert(step);
+    }
+
+    match edges.keys().filter(|s| !visited.contains(s)).min() {
+        Some(&s) => Err(OrderError::Missing(s)),
+        None => Ok(()),
+    }
+}
+
+// Most sets of done steps the count may memoise before giving up.
+const MAX_ORDERING_STATES: usize = 1 << 16;
+
+// Counts the valid orderings by memoising over the set of steps already
+// done. Those are the groups of steps that can be finished before the rest,
+// which dependencies keep few but independent steps don't: 16 independent
+// steps already give 2^16 of them. Returns `None` once more than
+// `MAX_ORDERING_STATES` sets are needed, for graphs of more than 64 steps,
+// or if the count doesn't fit.
+pub fn count_orderings(edges: &HashMap<char, Vec<char>>) -> Option<u128> {
+    if edges.len() > 64 {
+        return None;
+    }
+
+    let mut steps: Vec<char> = edges.keys().cloned().collect();
+    steps.sort();
+    let prerequisites: Vec<u64> = steps
+        .iter()
+        .map(|s| {
+            edges[s]
+                .iter()
+                .filter_map(|p| steps.iter().position(|x| x == p))
+                .fold(0, |mask, i| mask | 1 << i)
+        }).collect();
+
+    let mut memo: HashMap<u64, Option<u128>> = HashMap::new();
+    count_from(0, &prerequisites, &mut memo)
+}
+
+fn count_from(
+    done: u64,
+    prerequisites: &[u64],
+    memo: &mut HashMap<u64, Option<u128>>,
+) -> Option<u128> {
+    if done.count_ones() as usize == prerequisites.len() {
+        return Some(1);
+    }
+    if let Some(&n) = memo.get(&done) {
+        return n;
+    }
+    if memo.len() >= MAX_ORDERING_STATES {
+        return None;
+    }
+
+    let mut total: Option<u128> = Some(0);
+    for (i, &p) in prerequisites.iter().enumerate() {
+        if done & 1 << i == 0 && done & p == p {
+            total = match (total, count_from(done | 1 << i, prerequisites, memo)) {
+                (Some(t), Some(n)) => t.checked_add(n),
+                _ => None,
+            };
+            if total.is_none() {
+                break;
             }
         }
+    }
 
-        let mut next = next_steps(&edges, &visited);
-        if next.len() == 0 {
-            break;
+    memo.insert(done, total);
+    total
+}
+
+// Lazily yields every valid ordering, in lexicographic order.
+pub struct Orderings {
+    steps: Vec<char>,
+    prerequisites: Vec<Vec<usize>>,
+    placed: Vec<bool>,
+    order: Vec<usize>,
+    // Next candidate to try at each depth of `order`
+    cursor: Vec<usize>,
+    done: bool,
+}
+
+pub fn orderings(edges: &HashMap<char, Vec<char>>) -> Orderings {
+    let mut steps: Vec<char> = edges.keys().cloned().collect();
+    steps.sort();
+    let prerequisites = steps
+        .iter()
+        .map(|s| {
+            edges[s]
+                .iter()
+                .filter_map(|p| steps.iter().position(|x| x == p))
+                .collect()
+        }).collect();
+
+    Orderings {
+        placed: vec![false; steps.len()],
+        order: Vec::with_capacity(steps.len()),
+        cursor: vec![0],
+        steps,
+        prerequisites,
+        done: false,
+    }
+}
+
+impl Orderings {
+    fn backtrack(&mut self) {
+        self.cursor.pop();
+        match self.order.pop() {
+            Some(i) => self.placed[i] = false,
+            None => self.done = true,
         }
+    }
+}
 
-        next.sort();
-        for step in next {
-            if new_workers.len() >= n_workers {
-                break;
+impl Iterator for Orderings {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while !self.done {
+            let depth = self.order.len();
+            if depth == self.steps.len() {
+                let s = self.order.iter().map(|&i| self.steps[i]).collect();
+                self.backtrack();
+                return Some(s);
             }
 
-            if new_workers.iter().find(|w| w.1 == step).is_none() {
-                new_workers.push(Work(total_time, step));
+            let next = (self.cursor[depth]..self.steps.len()).find(|&i| {
+                !self.placed[i] && self.prerequisites[i].iter().all(|&p| self.placed[p])
+            });
+            match next {
+                Some(i) => {
+                    self.cursor[depth] = i + 1;
+                    self.placed[i] = true;
+                    self.order.push(i);
+                    self.cursor.push(0);
+                }
+                None => self.backtrack(),
             }
         }
 
-        workers = new_workers;
-        total_time += 1;
-        assert!(workers.len() <= n_workers);
+        None
     }
+}
+
+// Steps are named by an ASCII letter, which `input_generator` enforces, with
+// A taking one second longer than the base delay, B two seconds longer, and
+// so on. Case is ignored.
+pub fn time_required(step_delay: usize, step: char) -> usize {
+    assert!(step.is_ascii_alphabetic(), "step {:?} is not a letter", step);
+    step_delay + 1 + (step.to_ascii_uppercase() as u8 - b'A') as usize
+}
 
-    total_time
+#[aoc(day7, part2)]
+fn answer_2(edges: &HashMap<char, Vec<char>>) -> usize {
+    Scheduler::new(5, |&step: &char| time_required(60, step))
+        .run(edges)
+        .expect("input_generator rejects cycles")
+        .total_time
 }
 
 #[cfg(test)]
@@ -123,11 +426,242 @@ Step F must be finished before step E can begin.\
 
     #[test]
     fn examples_1() {
-        assert_eq!(answer_1(&input_generator(TEST_INPUT)), "CABDFE");
+        assert_eq!(answer_1(&input_generator(TEST_INPUT).unwrap()), "CABDFE");
     }
     #[test]
     fn examples_2() {
-        assert_eq!(answer_2(&input_generator(TEST_INPUT)), 253);
+        assert_eq!(answer_2(&input_generator(TEST_INPUT).unwrap()), 253);
+    }
+
+    #[test]
+    fn examples_2_without_delay() {
+        let edges = input_generator(TEST_INPUT).unwrap();
+        let schedule = Scheduler::new(2, |&step: &char| time_required(0, step))
+            .run(&edges)
+            .unwrap();
+        let order: String = schedule.tasks.iter().map(|t| t.step).collect();
+
+        assert_eq!(schedule.total_time, 15);
+        assert_eq!(order, "CAFBDE");
+    }
+
+    #[test]
+    fn examples_2_gantt() {
+        let edges = input_generator(TEST_INPUT).unwrap();
+        let schedule = Scheduler::new(2, |&step: &char| time_required(0, step))
+            .run(&edges)
+            .unwrap();
+
+        assert_eq!(
+            schedule.gantt(),
+            "\
+Second   Worker 1   Worker 2   Done
+   0        C          .
+   1        C          .
+   2        C          .
+   3        A          F       C
+   4        B          F       CA
+   5        B          F       CA
+   6        D          F       CAB
+   7        D          F       CAB
+   8        D          F       CAB
+   9        D          .       CABF
+  10        E          .       CABFD
+  11        E          .       CABFD
+  12        E          .       CABFD
+  13        E          .       CABFD
+  14        E          .       CABFD
+  15        .          .       CABFDE
+"
+        );
+    }
+
+    #[test]
+    fn examples_dot() {
+        assert_eq!(
+            to_dot(&input_generator(TEST_INPUT).unwrap()),
+            "\
+digraph instructions {
+    \"C\" [label=\"C (1)\"];
+    \"A\" [label=\"A (2)\"];
+    \"B\" [label=\"B (3)\"];
+    \"D\" [label=\"D (4)\"];
+    \"F\" [label=\"F (5)\"];
+    \"E\" [label=\"E (6)\"];
+    \"A\" -> \"B\";
+    \"A\" -> \"D\";
+    \"B\" -> \"E\";
+    \"C\" -> \"A\";
+    \"C\" -> \"F\";
+    \"D\" -> \"E\";
+    \"F\" -> \"E\";
+}
+"
+        );
+    }
+
+    #[test]
+    fn step_times() {
+        assert_eq!(1, time_required(0, 'A'));
+        assert_eq!(1, time_required(0, 'a'));
+        assert_eq!(86, time_required(60, 'Z'));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a letter")]
+    fn step_times_need_letters() {
+        time_required(0, '\u{e9}');
+    }
+
+    #[test]
+    fn dot_quotes_step_names() {
+        let mut edges = HashMap::new();
+        edges.insert('-', vec!['"']);
+        edges.insert('"', vec![]);
+
+        assert_eq!(
+            to_dot(&edges),
+            "\
+digraph instructions {
+    \"\\\"\" [label=\"\\\" (1)\"];
+    \"-\" [label=\"- (2)\"];
+    \"\\\"\" -> \"-\";
+}
+"
+        );
+    }
+
+    #[test]
+    fn examples_2_reach_lower_bound() {
+        let analysis = Scheduler::new(5, |&step: &char| time_required(60, step))
+            .analyse(&input_generator(TEST_INPUT).unwrap())
+            .unwrap();
+
+        assert_eq!(analysis.critical_path, vec!['C', 'A', 'D', 'E']);
+        assert_eq!(analysis.lower_bound, 253);
+        assert_eq!(analysis.total_time, 253);
+    }
+
+    #[test]
+    fn examples_orderings() {
+        let edges = input_generator(TEST_INPUT).unwrap();
+        let all: Vec<String> = orderings(&edges).collect();
+
+        assert_eq!(count_orderings(&edges), Some(8));
+        assert_eq!(all.len(), 8);
+        assert_eq!(all[0], answer_1(&edges));
+        assert_eq!(all[7], "CFADBE");
+        assert!(all.iter().all(|o| check_order(&edges, o).is_ok()));
+    }
+
+    #[test]
+    fn independent_steps_orderings() {
+        let edges: HashMap<char, Vec<char>> =
+            "ABCDEFGHIJKL".chars().map(|c| (c, vec![])).collect();
+
+        assert_eq!(count_orderings(&edges), Some((1..=12).product::<u128>()));
+        assert_eq!(orderings(&edges).nth(1), Some("ABCDEFGHIJLK".to_string()));
+    }
+
+    #[test]
+    fn count_orderings_gives_up_on_too_many_states() {
+        let independent: HashMap<char, Vec<char>> = ('A'..='Z').map(|c| (c, vec![])).collect();
+        assert_eq!(count_orderings(&independent), None);
+
+        let letters: Vec<char> = ('A'..='Z').chain('a'..='z').collect();
+        let chain: HashMap<char, Vec<char>> = letters
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (c, letters[..i].last().cloned().into_iter().collect()))
+            .collect();
+        assert_eq!(count_orderings(&chain), Some(1));
+    }
+
+    #[test]
+    fn check_order_reports_first_violation() {
+        let edges = input_generator(TEST_INPUT).unwrap();
+
+        assert_eq!(
+            check_order(&edges, "CAEBDF"),
+            Err(OrderError::Unsatisfied {
+                step: 'E',
+                prerequisite: 'B'
+            })
+        );
+        assert_eq!(check_order(&edges, "CAXBDF"), Err(OrderError::UnknownStep('X')));
+        assert_eq!(check_order(&edges, "CACBDF"), Err(OrderError::Repeated('C')));
+        assert_eq!(check_order(&edges, "CABD"), Err(OrderError::Missing('E')));
+    }
+
+    #[test]
+    fn rejects_malformed_instructions() {
+        assert_eq!(
+            GraphError::Parse {
+                line: 2,
+                text: "Step A must be done before step B can begin.".to_string(),
+            },
+            input_generator(
+                "\
+Step C must be finished before step A can begin.
+Step A must be done before step B can begin."
+            ).unwrap_err()
+        );
+        assert_eq!(
+            GraphError::Parse {
+                line: 1,
+                text: "Step 1 must be finished before step 2 can begin.".to_string(),
+            },
+            input_generator("Step 1 must be finished before step 2 can begin.").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn rejects_self_dependency() {
+        assert_eq!(
+            GraphError::SelfDependency('A'),
+            input_generator("Step A must be finished before step A can begin.").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_edges() {
+        assert_eq!(
+            GraphError::DuplicateEdge('A', 'B'),
+            input_generator(
+                "\
+Step A must be finished before step B can begin.
+Step A must be finished before step B can begin."
+            ).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn rejects_missing_steps() {
+        let mut edges = HashMap::new();
+        edges.insert('B', vec!['A']);
+
+        assert_eq!(
+            Err(GraphError::MissingStep {
+                step: 'B',
+                prerequisite: 'A'
+            }),
+            validate(&edges)
+        );
+    }
+
+    #[test]
+    fn reports_cycles_in_order() {
+        assert_eq!(
+            GraphError::Cycle(vec!['B', 'C', 'D']),
+            input_generator(
+                "\
+Step A must be finished before step B can begin.
+Step B must be finished before step C can begin.
+Step C must be finished before step D can begin.
+Step D must be finished before step B can begin.
+Step D must be finished before step E can begin."
+            ).unwrap_err()
+        );
     }
 
 }
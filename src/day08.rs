@@ -1,6 +1,9 @@
 use std::collections::VecDeque;
+use std::ops::RangeInclusive;
 
-#[derive(Debug)]
+// Trees can be far deeper than the stack, so `Clone`, `PartialEq`, `Debug`
+// and `Drop` are written by hand to walk them without recursing.
+#[derive(Eq)]
 pub struct Node {
     children: Vec<Node>,
     metadata: Vec<usize>,
@@ -11,16 +14,247 @@ impl Node {
         self.metadata.iter().sum()
     }
 
+    // Each child's value is computed once, however often the metadata refers
+    // to it.
     pub fn value(&self) -> usize {
-        if self.children.is_empty() {
-            self.sum()
-        } else {
-            self.metadata
-                .iter()
-                .flat_map(|i| self.children.get(i - 1).map(|n| n.value()))
-                .sum()
+        self.fold(|n, values: Vec<usize>| {
+            if values.is_empty() {
+                n.sum()
+            } else {
+                n.metadata
+                    .iter()
+                    .flat_map(|i| i.checked_sub(1).and_then(|i| values.get(i)))
+                    .sum()
+            }
+        })
+    }
+
+    // Folds the tree bottom up, handing each node the results for its
+    // children.
+    pub fn fold<B, F>(&self, mut f: F) -> B
+    where
+        F: FnMut(&Node, Vec<B>) -> B,
+    {
+        let mut stack: Vec<(&Node, usize)> = vec![(self, 0)];
+        let mut results: Vec<B> = Vec::new();
+
+        while let Some((n, i)) = stack.pop() {
+            if i < n.children.len() {
+                stack.push((n, i + 1));
+                stack.push((&n.children[i], 0));
+            } else {
+                let children = results.split_off(results.len() - n.children.len());
+                results.push(f(n, children));
+            }
+        }
+
+        results.pop().unwrap()
+    }
+
+    pub fn pre_order(&self) -> PreOrder<'_> {
+        PreOrder { stack: vec![self] }
+    }
+
+    pub fn post_order(&self) -> PostOrder<'_> {
+        PostOrder {
+            stack: vec![(self, 0)],
         }
     }
+
+    pub fn breadth_first(&self) -> BreadthFirst<'_> {
+        let mut queue = VecDeque::new();
+        queue.push_back(self);
+
+        BreadthFirst { queue }
+    }
+
+    // Pre-order traversal that also tracks where each node is in the tree.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            stack: vec![Entry {
+                node: self,
+                path: Vec::new(),
+            }],
+        }
+    }
+
+    // Renders the tree one node per line, indented by depth and labelled A,
+    // B, C and so on in pre-order.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+
+        for (i, e) in self.walk().enumerate() {
+            let metadata: Vec<String> = e.node.metadata.iter().map(|m| m.to_string()).collect();
+            out.push_str(&format!(
+                "{}{} [{}]\n",
+                "  ".repeat(e.depth()),
+                label(i),
+                metadata.join(", ")
+            ));
+        }
+
+        out
+    }
+
+    // Serialises the tree back into the flat format it is parsed from.
+    pub fn encode(&self) -> String {
+        enum Visit<'a> {
+            Header(&'a Node),
+            Metadata(&'a Node),
+        }
+
+        let mut numbers: Vec<String> = Vec::new();
+        let mut stack = vec![Visit::Header(self)];
+
+        while let Some(v) = stack.pop() {
+            match v {
+                Visit::Header(n) => {
+                    numbers.push(n.children.len().to_string());
+                    numbers.push(n.metadata.len().to_string());
+                    stack.push(Visit::Metadata(n));
+                    stack.extend(n.children.iter().rev().map(Visit::Header));
+                }
+                Visit::Metadata(n) => numbers.extend(n.metadata.iter().map(|m| m.to_string())),
+            }
+        }
+
+        numbers.join(" ")
+    }
+}
+
+pub struct PreOrder<'a> {
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Iterator for PreOrder<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        let n = self.stack.pop()?;
+        self.stack.extend(n.children.iter().rev());
+
+        Some(n)
+    }
+}
+
+pub struct PostOrder<'a> {
+    stack: Vec<(&'a Node, usize)>,
+}
+
+impl<'a> Iterator for PostOrder<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        while let Some((n, i)) = self.stack.pop() {
+            if i == n.children.len() {
+                return Some(n);
+            }
+
+            self.stack.push((n, i + 1));
+            self.stack.push((&n.children[i], 0));
+        }
+
+        None
+    }
+}
+
+pub struct BreadthFirst<'a> {
+    queue: VecDeque<&'a Node>,
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        let n = self.queue.pop_front()?;
+        self.queue.extend(n.children.iter());
+
+        Some(n)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub node: &'a Node,
+    // Child indices leading from the root to the node
+    pub path: Vec<usize>,
+}
+
+impl<'a> Entry<'a> {
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+}
+
+pub struct Walk<'a> {
+    stack: Vec<Entry<'a>>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Entry<'a>;
+
+    fn next(&mut self) -> Option<Entry<'a>> {
+        let e = self.stack.pop()?;
+        for (i, c) in e.node.children.iter().enumerate().rev() {
+            let mut path = e.path.clone();
+            path.push(i);
+            self.stack.push(Entry { node: c, path });
+        }
+
+        Some(e)
+    }
+}
+
+// Spreadsheet style labels: A to Z, then AA, AB and so on.
+fn label(mut i: usize) -> String {
+    let mut label = Vec::new();
+    loop {
+        label.push(b'A' + (i % 26) as u8);
+        if i < 26 {
+            break;
+        }
+        i = i / 26 - 1;
+    }
+    label.reverse();
+
+    String::from_utf8(label).unwrap()
+}
+
+// Children are dropped from an explicit stack as well, as the default
+// recursive drop would overflow on deep trees.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(mut n) = stack.pop() {
+            stack.append(&mut n.children);
+        }
+    }
+}
+
+impl Clone for Node {
+    fn clone(&self) -> Self {
+        self.fold(|n, children| Node {
+            children,
+            metadata: n.metadata.clone(),
+        })
+    }
+}
+
+// Pre-order with each node's number of children pins down the whole shape.
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        fn shape(n: &Node) -> (usize, &[usize]) {
+            (n.children.len(), &n.metadata)
+        }
+
+        self.pre_order().map(shape).eq(other.pre_order().map(shape))
+    }
+}
+
+impl std::fmt::Debug for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("Node").field(&self.encode()).finish()
+    }
 }
 
 impl AsRef<Node> for Node {
@@ -29,37 +263,244 @@ impl AsRef<Node> for Node {
     }
 }
 
-fn parse_node(i: &mut impl Iterator<Item = usize>) -> Node {
-    let n_children = i.next().expect("missing children count");
-    let n_metadata = i.next().expect("missing metadata count");
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidNumber(String),
+    UnexpectedEnd,
+    TrailingInput,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    // Position of the offending token in the input, or the number of tokens
+    // if the input ended early
+    pub index: usize,
+    // Child indices leading from the root to the node being parsed
+    pub path: Vec<usize>,
+    pub kind: ErrorKind,
+}
 
-    Node {
-        children: (0..n_children).map(|_| parse_node(i)).collect(),
-        metadata: (0..n_metadata)
-            .map(|_| i.next().expect("missing metadata"))
-            .collect(),
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.kind {
+            ErrorKind::InvalidNumber(t) => write!(f, "invalid number {:?}", t)?,
+            ErrorKind::UnexpectedEnd => write!(f, "unexpected end of input")?,
+            ErrorKind::TrailingInput => write!(f, "unexpected trailing input")?,
+        }
+
+        write!(f, " at token {} (node path {:?})", self.index, self.path)
     }
 }
 
-#[aoc_generator(day8)]
-pub fn input_generator(input: &str) -> Node {
-    let mut i = input.trim().split(' ').map(|x| x.parse::<usize>().unwrap());
+impl std::error::Error for ParseError {}
 
-    parse_node(&mut i)
+// A node whose header has been read, but not yet all of its children or
+// metadata.
+struct Partial {
+    n_children: usize,
+    n_metadata: usize,
+    children: Vec<Node>,
 }
 
-#[aoc(day8, part1)]
-fn answer_1(input: &Node) -> usize {
-    let mut sum: usize = 0;
-    let mut q = VecDeque::new();
-    q.push_back(input);
+struct Tokens<'a> {
+    tokens: std::iter::Enumerate<std::str::SplitWhitespace<'a>>,
+    consumed: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn next(&mut self, path: &[usize]) -> Result<usize, ParseError> {
+        let error = |index, kind| ParseError {
+            index,
+            path: path.to_vec(),
+            kind,
+        };
+
+        match self.tokens.next() {
+            Some((i, t)) => {
+                self.consumed = i + 1;
+                t.parse()
+                    .map_err(|_| error(i, ErrorKind::InvalidNumber(t.to_string())))
+            }
+            None => Err(error(self.consumed, ErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn header(&mut self, path: &[usize]) -> Result<Partial, ParseError> {
+        let n_children = self.next(path)?;
+        let n_metadata = self.next(path)?;
+
+        Ok(Partial {
+            n_children,
+            n_metadata,
+            children: Vec::with_capacity(n_children.min(1024)),
+        })
+    }
+}
+
+// Parses the tree with an explicit stack rather than recursion, so that
+// arbitrarily deep trees can't overflow the call stack.
+pub fn parse(input: &str) -> Result<Node, ParseError> {
+    let mut tokens = Tokens {
+        tokens: input.split_whitespace().enumerate(),
+        consumed: 0,
+    };
+    let mut path: Vec<usize> = Vec::new();
+    let mut stack = vec![tokens.header(&path)?];
+
+    let root = loop {
+        let top = stack.last_mut().unwrap();
+
+        if top.children.len() < top.n_children {
+            path.push(top.children.len());
+            let child = tokens.header(&path)?;
+            stack.push(child);
+            continue;
+        }
 
-    while let Some(n) = q.pop_front() {
-        q.extend(n.children.iter());
-        sum += n.sum();
+        let mut metadata = Vec::with_capacity(top.n_metadata.min(1024));
+        for _ in 0..top.n_metadata {
+            metadata.push(tokens.next(&path)?);
+        }
+
+        let partial = stack.pop().unwrap();
+        let node = Node {
+            children: partial.children,
+            metadata,
+        };
+
+        match stack.last_mut() {
+            Some(parent) => {
+                parent.children.push(node);
+                path.pop();
+            }
+            None => break node,
+        }
+    };
+
+    match tokens.tokens.next() {
+        Some((index, _)) => Err(ParseError {
+            index,
+            path: Vec::new(),
+            kind: ErrorKind::TrailingInput,
+        }),
+        None => Ok(root),
     }
+}
 
-    sum
+#[derive(Debug, Clone)]
+pub struct TreeConfig {
+    // Nodes at this depth are always leaves
+    pub max_depth: usize,
+    // Nodes get fewer children, or none, rather than going over this many
+    // in total
+    pub max_nodes: usize,
+    pub children: RangeInclusive<usize>,
+    pub metadata: RangeInclusive<usize>,
+    pub values: RangeInclusive<usize>,
+}
+
+// xorshift64*, which is plenty for generating test data.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // One round of splitmix64 so that nearby seeds give unrelated states
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        Rng((z ^ (z >> 31)) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Expects a non-empty range. A span of zero means the range covers every
+    // `u64`, when any number will do.
+    fn range(&mut self, r: &RangeInclusive<usize>) -> usize {
+        let span = ((r.end() - r.start()) as u64).wrapping_add(1);
+        match span {
+            0 => r.start() + self.next() as usize,
+            span => r.start() + (self.next() % span) as usize,
+        }
+    }
+}
+
+// Generates a tree of the given shape, always the same one for the same
+// seed.
+pub fn random_tree(seed: u64, config: &TreeConfig) -> Node {
+    for (name, range) in [
+        ("children", &config.children),
+        ("metadata", &config.metadata),
+        ("values", &config.values),
+    ] {
+        assert!(!range.is_empty(), "empty {} range {:?}", name, range);
+    }
+
+    let mut rng = Rng::new(seed);
+    // Nodes made so far plus children promised to nodes on the stack
+    let mut nodes = 1;
+    let mut stack = vec![Partial {
+        n_children: rng.range(&config.children),
+        n_metadata: rng.range(&config.metadata),
+        children: Vec::new(),
+    }];
+    if config.max_depth == 0 {
+        stack[0].n_children = 0;
+    }
+    stack[0].n_children = stack[0]
+        .n_children
+        .min(config.max_nodes.saturating_sub(nodes));
+    nodes += stack[0].n_children;
+
+    loop {
+        let depth = stack.len();
+        let top = stack.last_mut().unwrap();
+
+        if top.children.len() < top.n_children {
+            let mut n_children = rng.range(&config.children);
+            if depth >= config.max_depth {
+                n_children = 0;
+            }
+            n_children = n_children.min(config.max_nodes.saturating_sub(nodes));
+            nodes += n_children;
+
+            let child = Partial {
+                n_children,
+                n_metadata: rng.range(&config.metadata),
+                children: Vec::with_capacity(n_children),
+            };
+            stack.push(child);
+            continue;
+        }
+
+        let partial = stack.pop().unwrap();
+        let node = Node {
+            metadata: (0..partial.n_metadata)
+                .map(|_| rng.range(&config.values))
+                .collect(),
+            children: partial.children,
+        };
+
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => return node,
+        }
+    }
+}
+
+#[aoc_generator(day8)]
+pub fn input_generator(input: &str) -> Result<Node, ParseError> {
+    parse(input)
+}
+
+#[aoc(day8, part1)]
+fn answer_1(input: &Node) -> usize {
+    input.pre_order().map(Node::sum).sum()
 }
 
 #[aoc(day8, part2)]
@@ -74,11 +515,253 @@ mod test {
 
     #[test]
     fn examples_1() {
-        assert_eq!(138, answer_1(&input_generator(TEST_INPUT)));
+        assert_eq!(138, answer_1(&input_generator(TEST_INPUT).unwrap()));
     }
 
     #[test]
     fn examples_2() {
-        assert_eq!(66, answer_2(&input_generator(TEST_INPUT)));
+        assert_eq!(66, answer_2(&input_generator(TEST_INPUT).unwrap()));
+    }
+
+    #[test]
+    fn traversal_orders() {
+        let root = input_generator(TEST_INPUT).unwrap();
+        let firsts =
+            |nodes: Vec<&Node>| -> Vec<usize> { nodes.iter().map(|n| n.metadata[0]).collect() };
+
+        assert_eq!(vec![1, 10, 2, 99], firsts(root.pre_order().collect()));
+        assert_eq!(vec![10, 99, 2, 1], firsts(root.post_order().collect()));
+        assert_eq!(vec![1, 10, 2, 99], firsts(root.breadth_first().collect()));
+
+        let root = parse("2 1 1 1 0 1 7 1 0 1 8 2").unwrap();
+        assert_eq!(vec![2, 1, 8, 7], firsts(root.breadth_first().collect()));
+        assert_eq!(vec![2, 1, 7, 8], firsts(root.pre_order().collect()));
+    }
+
+    #[test]
+    fn walk_paths() {
+        let root = input_generator(TEST_INPUT).unwrap();
+        let paths: Vec<(Vec<usize>, usize)> =
+            root.walk().map(|e| (e.path.clone(), e.depth())).collect();
+
+        assert_eq!(
+            vec![(vec![], 0), (vec![0], 1), (vec![1], 1), (vec![1, 0], 2)],
+            paths
+        );
+    }
+
+    #[test]
+    fn fold_counts_nodes() {
+        let root = input_generator(TEST_INPUT).unwrap();
+
+        assert_eq!(4, root.fold(|_, c: Vec<usize>| 1 + c.iter().sum::<usize>()));
+        assert_eq!(
+            3,
+            root.fold(|_, c: Vec<usize>| 1 + c.into_iter().max().unwrap_or(0))
+        );
+    }
+
+    #[test]
+    fn value_with_repeated_references() {
+        // Every level refers to its only child many times over
+        let depth = 30;
+        let mut input = "1 4 ".repeat(depth);
+        input.push_str("0 1 1");
+        input.push_str(&" 1 1 1 1".repeat(depth));
+
+        assert_eq!(1usize << (2 * depth), parse(&input).unwrap().value());
+    }
+
+    #[test]
+    fn pretty_examples() {
+        assert_eq!(
+            "\
+A [1, 1, 2]
+  B [10, 11, 12]
+  C [2]
+    D [99]
+",
+            input_generator(TEST_INPUT).unwrap().pretty()
+        );
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!("A", label(0));
+        assert_eq!("Z", label(25));
+        assert_eq!("AA", label(26));
+        assert_eq!("AZ", label(51));
+        assert_eq!("BA", label(52));
+    }
+
+    fn config() -> TreeConfig {
+        TreeConfig {
+            max_depth: 6,
+            max_nodes: 1000,
+            children: 0..=4,
+            metadata: 1..=3,
+            values: 0..=5,
+        }
+    }
+
+    #[test]
+    fn encode_examples() {
+        let root = input_generator(TEST_INPUT).unwrap();
+        assert_eq!(TEST_INPUT, root.encode());
+    }
+
+    #[test]
+    fn encode_round_trips() {
+        for seed in 0..100 {
+            let root = random_tree(seed, &config());
+            let encoded = root.encode();
+            assert_eq!(
+                Ok(encoded.clone()),
+                parse(&encoded).map(|n| n.encode()),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn random_trees_follow_config() {
+        let config = config();
+        let root = random_tree(42, &config);
+
+        assert_eq!(root, random_tree(42, &config));
+        assert_ne!(root, random_tree(43, &config));
+
+        let mut q = vec![(&root, 0)];
+        let mut nodes = 0;
+        while let Some((n, depth)) = q.pop() {
+            nodes += 1;
+            assert!(depth <= config.max_depth);
+            assert!(n.children.len() <= *config.children.end());
+            assert!(config.metadata.contains(&n.metadata.len()));
+            assert!(n.metadata.iter().all(|m| config.values.contains(m)));
+            q.extend(n.children.iter().map(|c| (c, depth + 1)));
+        }
+        assert!(nodes <= config.max_nodes);
+    }
+
+    #[test]
+    fn random_trees_stop_at_max_nodes() {
+        let config = TreeConfig {
+            max_nodes: 10,
+            children: 3..=4,
+            ..config()
+        };
+
+        for seed in 0..100 {
+            assert_eq!(
+                10,
+                random_tree(seed, &config).pre_order().count(),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn full_width_random_ranges() {
+        let config = TreeConfig {
+            max_nodes: 50,
+            children: 0..=usize::MAX,
+            values: 0..=usize::MAX,
+            ..config()
+        };
+
+        let root = random_tree(3, &config);
+        assert_eq!(50, root.pre_order().count());
+        assert!(root
+            .pre_order()
+            .any(|n| n.metadata.iter().any(|&m| m > u32::MAX as usize)));
+    }
+
+    #[test]
+    #[should_panic(expected = "empty metadata range")]
+    fn empty_random_ranges() {
+        let config = TreeConfig {
+            metadata: RangeInclusive::new(3, 1),
+            ..config()
+        };
+        random_tree(0, &config);
+    }
+
+    #[test]
+    fn large_random_trees() {
+        let config = TreeConfig {
+            max_depth: 10_000,
+            max_nodes: 200_000,
+            children: 0..=3,
+            metadata: 1..=3,
+            values: 1..=10,
+        };
+        let root = random_tree(7, &config);
+        let input = root.encode();
+        let parsed = parse(&input).unwrap();
+
+        assert_eq!(answer_1(&root), answer_1(&parsed));
+        assert_eq!(root.value(), parsed.value());
+    }
+
+    #[test]
+    fn parses_deep_trees() {
+        let depth = 1_000_000;
+        let mut input = "1 1 ".repeat(depth);
+        input.push_str("0 1 7");
+        input.push_str(&" 1".repeat(depth));
+
+        let root = parse(&input).unwrap();
+        assert_eq!(7 + depth, answer_1(&root));
+
+        let copy = root.clone();
+        assert!(copy == root);
+        assert!(copy != parse(&input.replacen("0 1 7", "0 1 8", 1)).unwrap());
+    }
+
+    #[test]
+    fn reports_truncated_input() {
+        assert_eq!(
+            Err(ParseError {
+                index: 15,
+                path: vec![],
+                kind: ErrorKind::UnexpectedEnd,
+            }),
+            parse("2 3 0 3 10 11 12 1 1 0 1 99 2 1 1")
+        );
+        assert_eq!(
+            Err(ParseError {
+                index: 6,
+                path: vec![1, 0],
+                kind: ErrorKind::UnexpectedEnd,
+            }),
+            parse("2 0 0 0 1 0")
+        );
+    }
+
+    #[test]
+    fn reports_invalid_numbers() {
+        assert_eq!(
+            Err(ParseError {
+                index: 10,
+                path: vec![1, 0],
+                kind: ErrorKind::InvalidNumber("x".to_string()),
+            }),
+            parse("2 3 0 3 10 11 12 1 1 0 x 99 2 1 1 2")
+        );
+    }
+
+    #[test]
+    fn rejects_trailing_input() {
+        assert_eq!(
+            Err(ParseError {
+                index: 16,
+                path: vec![],
+                kind: ErrorKind::TrailingInput,
+            }),
+            parse("2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2 5")
+        );
     }
 }
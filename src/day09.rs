@@ -1,6 +1,13 @@
-use std::collections::VecDeque;
+use regex::Regex;
+use ring::Ring;
 
-#[derive(Debug)]
+// Wide enough that no game with a `usize` number of marbles can overflow it.
+pub type Score = u128;
+
+// Part 2 plays with a hundred times as many marbles.
+const PART_2_MULTIPLIER: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Input {
     players: usize,
     last_marble_value: usize,
@@ -12,86 +19,277 @@ impl AsRef<Input> for Input {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    // Marbles that are a multiple of this score instead of being placed
+    pub scoring_multiple: usize,
+    // How far counter-clockwise the marble removed when scoring is
+    pub removal_offset: usize,
+    // How far clockwise a new marble is placed
+    pub placement_offset: usize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            scoring_multiple: 23,
+            removal_offset: 7,
+            placement_offset: 2,
+        }
+    }
+}
+
 pub struct Circle {
     marble: usize,
-    marbles: VecDeque<usize>,
+    marbles: Ring<usize>,
+    rules: Rules,
 }
 
 impl Circle {
-    fn new() -> Self {
-        let mut marbles = VecDeque::new();
-        marbles.push_back(0);
-
+    fn new(rules: Rules) -> Self {
         Circle {
             marble: 0,
-            marbles: marbles,
+            marbles: Ring::new(0),
+            rules,
         }
     }
 
-    pub fn place(&mut self) -> Option<usize> {
+    // With a scoring multiple of one the first marble takes marble 0 and
+    // leaves the circle empty. Scoring marbles then have nothing to take, so
+    // they score just themselves.
+    pub fn place(&mut self) -> Option<Score> {
         self.marble += 1;
 
-        if self.marble % 23 == 0 {
-            for _ in 0..7 {
-                let m = self.marbles.pop_back().unwrap();
-                self.marbles.push_front(m);
-            }
-            Some(self.marble + self.marbles.pop_front().unwrap())
+        if self.marble.is_multiple_of(self.rules.scoring_multiple) {
+            self.marbles.rotate(-(self.rules.removal_offset as isize));
+            let taken = self.marbles.remove().map_or(0, |m| m as Score);
+            Some(self.marble as Score + taken)
         } else {
-            for _ in 0..2 {
-                let m = self.marbles.pop_front().unwrap();
-                self.marbles.push_back(m);
-            }
-            self.marbles.push_front(self.marble);
+            self.marbles
+                .rotate(self.rules.placement_offset as isize - 1);
+            self.marbles.insert(self.marble);
             None
         }
     }
+
+    // Lists the marbles clockwise from 0 as in the puzzle, with the current
+    // marble in parentheses. An empty circle renders as nothing.
+    pub fn render(&self) -> String {
+        let marbles: Vec<usize> = self.marbles.iter().cloned().collect();
+        if marbles.is_empty() {
+            return String::new();
+        }
+
+        let zero = marbles.iter().position(|&m| m == 0).unwrap_or(0);
+        let current = (marbles.len() - zero) % marbles.len();
+
+        let mut out = String::new();
+        for (i, m) in marbles[zero..]
+            .iter()
+            .chain(marbles[..zero].iter())
+            .enumerate()
+        {
+            let cell = if i == current {
+                format!("{:>3}", format!("({}", m))
+            } else {
+                format!("{:>3}", m)
+            };
+
+            if i > 0 && i - 1 == current {
+                out.push(')');
+                out.push_str(cell.strip_prefix(' ').unwrap_or(&cell));
+            } else {
+                out.push_str(&cell);
+            }
+        }
+        if current == marbles.len() - 1 {
+            out.push(')');
+        }
+
+        out
+    }
+}
+
+// The marble placed on each turn is numbered after the turn, so `turn` is
+// also the marble that scored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoring {
+    pub turn: usize,
+    pub player: usize,
+    pub points: Score,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lead {
+    pub turn: usize,
+    pub player: usize,
+    pub score: Score,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameResult {
+    pub scores: Vec<Score>,
+    // Lowest numbered player with the highest score
+    pub winner: usize,
+    pub scoring: Vec<Scoring>,
+    // Every turn on which the lead changed hands
+    pub leaders: Vec<Lead>,
+}
+
+impl GameResult {
+    pub fn winning_score(&self) -> Score {
+        self.scores[self.winner]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    Format(String),
+    Number(String),
+    NoPlayers,
+    // Too many marbles to number them all for part 2
+    TooManyMarbles(usize),
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InputError::Format(s) => write!(f, "unrecognised game description {:?}", s),
+            InputError::Number(s) => write!(f, "number out of range: {}", s),
+            InputError::NoPlayers => write!(f, "the game needs at least one player"),
+            InputError::TooManyMarbles(n) => {
+                write!(f, "a last marble worth {} points is too many marbles", n)
+            }
+        }
+    }
 }
 
+impl std::error::Error for InputError {}
+
 #[aoc_generator(day9)]
-pub fn input_generator(input: &str) -> Input {
-    let mut i = input.trim().split(' ');
+pub fn input_generator(input: &str) -> Result<Input, InputError> {
+    lazy_static! {
+        static ref RE: Regex =
+            Regex::new(r"^(\d+) players?; last marble is worth (\d+) points?$").unwrap();
+    }
 
-    Input {
-        players: i.nth(0).unwrap().parse().unwrap(),
-        last_marble_value: i.nth(5).unwrap().parse().unwrap(),
+    let input = input.trim();
+    let c = RE
+        .captures(input)
+        .ok_or_else(|| InputError::Format(input.to_string()))?;
+    let number = |s: &str| s.parse().map_err(|_| InputError::Number(s.to_string()));
+
+    let players = number(&c[1])?;
+    if players == 0 {
+        return Err(InputError::NoPlayers);
+    }
+
+    let last_marble_value: usize = number(&c[2])?;
+    if last_marble_value.checked_mul(PART_2_MULTIPLIER).is_none() {
+        return Err(InputError::TooManyMarbles(last_marble_value));
     }
+
+    Ok(Input {
+        players,
+        last_marble_value,
+    })
 }
 
-fn game(input: &Input, multiplier: usize) -> usize {
-    let mut players: Vec<usize> = (0..input.players).map(|_| 0).collect();
-    let mut circle = Circle::new();
+fn game(input: &Input, multiplier: usize) -> Score {
+    play(input, multiplier, Rules::default()).winning_score()
+}
+
+pub fn play(input: &Input, multiplier: usize, rules: Rules) -> GameResult {
+    play_with(input, multiplier, rules, |_, _, _| {})
+}
+
+// Plays the game, calling `observe` with the turn, player and circle after
+// every turn. `input_generator` makes sure the multiplier used for part 2
+// can't overflow the number of marbles.
+pub fn play_with<F>(input: &Input, multiplier: usize, rules: Rules, mut observe: F) -> GameResult
+where
+    F: FnMut(usize, usize, &Circle),
+{
+    let mut scores: Vec<Score> = (0..input.players).map(|_| 0).collect();
+    let mut scoring = Vec::new();
+    let mut leaders: Vec<Lead> = Vec::new();
+    let mut circle = Circle::new(rules);
 
-    for (_, player) in (1..=input.last_marble_value * multiplier).zip((0..players.len()).cycle()) {
-        if let Some(value) = circle.place() {
-            players[player] += value;
+    let last_marble = input
+        .last_marble_value
+        .checked_mul(multiplier)
+        .expect("too many marbles");
+    let marbles = 1..=last_marble;
+    for (turn, player) in marbles.zip((0..scores.len()).cycle()) {
+        if let Some(points) = circle.place() {
+            scores[player] += points;
+            scoring.push(Scoring {
+                turn,
+                player,
+                points,
+            });
+
+            let leading = leaders.last().map_or(0, |l| l.score);
+            if scores[player] > leading {
+                if leaders.last().is_none_or(|l| l.player != player) {
+                    leaders.push(Lead {
+                        turn,
+                        player,
+                        score: scores[player],
+                    });
+                } else {
+                    leaders.last_mut().unwrap().score = scores[player];
+                }
+            }
         }
+
+        observe(turn, player, &circle);
+    }
+
+    let winner = (0..scores.len()).rev().max_by_key(|&p| scores[p]).unwrap();
+
+    GameResult {
+        scores,
+        winner,
+        scoring,
+        leaders,
     }
+}
 
-    *players.iter().max().unwrap()
+// Renders the circle after every turn in the puzzle's `[player] marbles`
+// format. Only sensible for small games.
+pub fn trace(input: &Input, rules: Rules) -> String {
+    let mut out = format!("[-]{}\n", Circle::new(rules.clone()).render());
+    play_with(input, 1, rules, |_, player, circle| {
+        out.push_str(&format!("[{}]{}\n", player + 1, circle.render()));
+    });
+
+    out
 }
 
 #[aoc(day9, part1)]
-fn answer_1(input: &Input) -> usize {
+fn answer_1(input: &Input) -> Score {
     game(input, 1)
 }
 
 #[aoc(day9, part2)]
-fn answer_2(input: &Input) -> usize {
-    game(input, 100)
+fn answer_2(input: &Input) -> Score {
+    game(input, PART_2_MULTIPLIER)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn input(s: &str) -> Input {
+        input_generator(s).unwrap()
+    }
+
     #[test]
     fn examples_p1_1() {
         assert_eq!(
             32,
-            answer_1(&input_generator(
-                "9 players; last marble is worth 32 points"
-            ))
+            answer_1(&input("9 players; last marble is worth 32 points"))
         );
     }
 
@@ -99,9 +297,7 @@ mod test {
     fn examples_p1_2() {
         assert_eq!(
             8317,
-            answer_1(&input_generator(
-                "10 players; last marble is worth 1618 points"
-            ))
+            answer_1(&input("10 players; last marble is worth 1618 points"))
         );
     }
 
@@ -109,9 +305,7 @@ mod test {
     fn examples_p1_3() {
         assert_eq!(
             146373,
-            answer_1(&input_generator(
-                "13 players; last marble is worth 7999 points"
-            ))
+            answer_1(&input("13 players; last marble is worth 7999 points"))
         );
     }
 
@@ -119,9 +313,7 @@ mod test {
     fn examples_p1_4() {
         assert_eq!(
             2764,
-            answer_1(&input_generator(
-                "17 players; last marble is worth 1104 points"
-            ))
+            answer_1(&input("17 players; last marble is worth 1104 points"))
         );
     }
 
@@ -129,19 +321,220 @@ mod test {
     fn examples_p1_5() {
         assert_eq!(
             54718,
-            answer_1(&input_generator(
-                "21 players; last marble is worth 6111 points"
-            ))
+            answer_1(&input("21 players; last marble is worth 6111 points"))
+        );
+    }
+
+    #[test]
+    fn examples_p2_1() {
+        assert_eq!(
+            22563,
+            answer_2(&input("9 players; last marble is worth 25 points"))
+        );
+    }
+
+    #[test]
+    fn examples_p2_2() {
+        assert_eq!(
+            74765078,
+            answer_2(&input("10 players; last marble is worth 1618 points"))
+        );
+    }
+
+    #[test]
+    fn examples_p2_3() {
+        assert_eq!(
+            1406506154,
+            answer_2(&input("13 players; last marble is worth 7999 points"))
+        );
+    }
+
+    #[test]
+    fn examples_p2_4() {
+        assert_eq!(
+            20548882,
+            answer_2(&input("17 players; last marble is worth 1104 points"))
+        );
+    }
+
+    #[test]
+    fn examples_p2_5() {
+        assert_eq!(
+            507583214,
+            answer_2(&input("21 players; last marble is worth 6111 points"))
+        );
+    }
+
+    #[test]
+    fn examples_p2_6() {
+        assert_eq!(
+            320997431,
+            answer_2(&input("30 players; last marble is worth 5807 points"))
+        );
+    }
+
+    #[test]
+    fn parser_accepts_singular_and_whitespace() {
+        assert_eq!(
+            Input {
+                players: 1,
+                last_marble_value: 1,
+            },
+            input(" 1 player; last marble is worth 1 point\n")
+        );
+    }
+
+    #[test]
+    fn parser_rejects_bad_input() {
+        assert_eq!(
+            Err(InputError::Format(
+                "10 players; last marble is 1618 points".to_string()
+            )),
+            input_generator("10 players; last marble is 1618 points")
+        );
+        assert_eq!(
+            Err(InputError::Number("99999999999999999999999".to_string())),
+            input_generator("99999999999999999999999 players; last marble is worth 1 points")
+        );
+        assert_eq!(
+            Err(InputError::NoPlayers),
+            input_generator("0 players; last marble is worth 1618 points")
+        );
+        assert_eq!(
+            Err(InputError::TooManyMarbles(1_000_000_000_000_000_000)),
+            input_generator("10 players; last marble is worth 1000000000000000000 points")
+        );
+    }
+
+    #[test]
+    fn scores_do_not_overflow() {
+        let marble = usize::MAX / 23 * 23;
+        let mut circle = Circle::new(Rules::default());
+        circle.marble = marble - 1;
+        circle.marbles = Ring::new(usize::MAX);
+
+        assert_eq!(Some(marble as Score + usize::MAX as Score), circle.place());
+    }
+
+    #[test]
+    fn examples_result() {
+        let result = play(
+            &input("9 players; last marble is worth 25 points"),
+            1,
+            Rules::default(),
+        );
+
+        assert_eq!(vec![0, 0, 0, 0, 32, 0, 0, 0, 0], result.scores);
+        assert_eq!(4, result.winner);
+        assert_eq!(
+            vec![Scoring {
+                turn: 23,
+                player: 4,
+                points: 32,
+            }],
+            result.scoring
+        );
+        assert_eq!(
+            vec![Lead {
+                turn: 23,
+                player: 4,
+                score: 32,
+            }],
+            result.leaders
+        );
+    }
+
+    #[test]
+    fn leaders_over_time() {
+        let result = play(
+            &input("10 players; last marble is worth 1618 points"),
+            1,
+            Rules::default(),
+        );
+
+        assert_eq!(8317, result.winning_score());
+        assert_eq!(result.scoring.len(), 1618 / 23);
+        assert!(result.scoring.iter().all(|s| s.turn % 23 == 0));
+        assert_eq!(result.winner, result.leaders.last().unwrap().player);
+        assert_eq!(8317, result.leaders.last().unwrap().score);
+        assert!(result
+            .leaders
+            .windows(2)
+            .all(|w| w[0].player != w[1].player && w[0].score < w[1].score));
+    }
+
+    #[test]
+    fn examples_trace() {
+        let trace = trace(
+            &input("9 players; last marble is worth 25 points"),
+            Rules::default(),
+        );
+
+        assert_eq!(
+            "\
+[-] (0)
+[1]  0 (1)
+[2]  0 (2) 1
+[3]  0  2  1 (3)
+[4]  0 (4) 2  1  3
+[5]  0  4  2 (5) 1  3
+[6]  0  4  2  5  1 (6) 3
+[7]  0  4  2  5  1  6  3 (7)
+[8]  0 (8) 4  2  5  1  6  3  7
+[9]  0  8  4 (9) 2  5  1  6  3  7
+[1]  0  8  4  9  2(10) 5  1  6  3  7
+[2]  0  8  4  9  2 10  5(11) 1  6  3  7
+[3]  0  8  4  9  2 10  5 11  1(12) 6  3  7
+[4]  0  8  4  9  2 10  5 11  1 12  6(13) 3  7
+[5]  0  8  4  9  2 10  5 11  1 12  6 13  3(14) 7
+[6]  0  8  4  9  2 10  5 11  1 12  6 13  3 14  7(15)
+[7]  0(16) 8  4  9  2 10  5 11  1 12  6 13  3 14  7 15
+[8]  0 16  8(17) 4  9  2 10  5 11  1 12  6 13  3 14  7 15
+[9]  0 16  8 17  4(18) 9  2 10  5 11  1 12  6 13  3 14  7 15
+[1]  0 16  8 17  4 18  9(19) 2 10  5 11  1 12  6 13  3 14  7 15
+[2]  0 16  8 17  4 18  9 19  2(20)10  5 11  1 12  6 13  3 14  7 15
+[3]  0 16  8 17  4 18  9 19  2 20 10(21) 5 11  1 12  6 13  3 14  7 15
+[4]  0 16  8 17  4 18  9 19  2 20 10 21  5(22)11  1 12  6 13  3 14  7 15
+[5]  0 16  8 17  4 18(19) 2 20 10 21  5 22 11  1 12  6 13  3 14  7 15
+[6]  0 16  8 17  4 18 19  2(24)20 10 21  5 22 11  1 12  6 13  3 14  7 15
+[7]  0 16  8 17  4 18 19  2 24 20(25)10 21  5 22 11  1 12  6 13  3 14  7 15
+",
+            trace
         );
     }
 
+    #[test]
+    fn custom_rules() {
+        let input = input("2 players; last marble is worth 10 points");
+        let rules = Rules {
+            scoring_multiple: 5,
+            removal_offset: 2,
+            placement_offset: 1,
+        };
+
+        // Each marble goes straight after the previous one, so marble 5
+        // takes 2 and marble 10 takes 7.
+        assert_eq!(17, play(&input, 1, rules).winning_score());
+    }
+
+    #[test]
+    fn every_marble_scoring() {
+        let input = input("2 players; last marble is worth 4 points");
+        let rules = Rules {
+            scoring_multiple: 1,
+            ..Rules::default()
+        };
+
+        // Marble 1 takes marble 0, and the rest find the circle empty.
+        assert_eq!(vec![4, 6], play(&input, 1, rules.clone()).scores);
+        assert_eq!("[-] (0)\n[1]\n[2]\n[1]\n[2]\n", trace(&input, rules));
+    }
+
     #[test]
     fn examples_p1_6() {
         assert_eq!(
             37305,
-            answer_1(&input_generator(
-                "30 players; last marble is worth 5807 points"
-            ))
+            answer_1(&input("30 players; last marble is worth 5807 points"))
         );
     }
 }
@@ -0,0 +1,316 @@
+use point::Point;
+use regex::Regex;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Light {
+    pub position: Point,
+    pub velocity: Point,
+}
+
+impl Light {
+    pub fn at(&self, seconds: i64) -> Point {
+        Point::new(
+            self.position.x() + self.velocity.x() * seconds,
+            self.position.y() + self.velocity.y() * seconds,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid light on line {}: {:?}", self.line, self.text)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[aoc_generator(day10)]
+pub fn input_generator(input: &str) -> Result<Vec<Light>, ParseError> {
+    lazy_static! {
+        static ref RE: Regex =
+            Regex::new(r"^position=<\s*(-?\d+),\s*(-?\d+)>\s*velocity=<\s*(-?\d+),\s*(-?\d+)>$")
+                .unwrap();
+    }
+
+    input
+        .trim()
+        .lines()
+        .enumerate()
+        .map(|(i, l)| {
+            let error = || ParseError {
+                line: i + 1,
+                text: l.to_string(),
+            };
+            let c = RE.captures(l.trim()).ok_or_else(error)?;
+            let number = |n: usize| c[n].parse().map_err(|_| error());
+
+            Ok(Light {
+                position: Point::new(number(1)?, number(2)?),
+                velocity: Point::new(number(3)?, number(4)?),
+            })
+        })
+        .collect()
+}
+
+pub fn positions(lights: &[Light], seconds: i64) -> Vec<Point> {
+    lights.iter().map(|l| l.at(seconds)).collect()
+}
+
+fn bounds(points: &[Point]) -> Option<(Point, Point)> {
+    let first = points.first()?;
+    Some(
+        points
+            .iter()
+            .fold((first.clone(), first.clone()), |(min, max), p| {
+                (min.min(p), max.max(p))
+            }),
+    )
+}
+
+fn area(lights: &[Light], seconds: i64) -> i64 {
+    bounds(&positions(lights, seconds)).map_or(0, |(min, max)| {
+        (max.x() - min.x() + 1) * (max.y() - min.y() + 1)
+    })
+}
+
+// The least squares estimate of when the lights are closest together, which
+// minimises the sum of their squared distances from their centroid.
+fn estimate(lights: &[Light]) -> i64 {
+    let n = lights.len() as f64;
+    let mean = |f: &dyn Fn(&Light) -> i64| lights.iter().map(|l| f(l) as f64).sum::<f64>() / n;
+    let (px, py) = (mean(&|l| l.position.x()), mean(&|l| l.position.y()));
+    let (vx, vy) = (mean(&|l| l.velocity.x()), mean(&|l| l.velocity.y()));
+
+    let (dot, speed) = lights.iter().fold((0.0, 0.0), |(dot, speed), l| {
+        let (dx, dy) = (l.position.x() as f64 - px, l.position.y() as f64 - py);
+        let (dvx, dvy) = (l.velocity.x() as f64 - vx, l.velocity.y() as f64 - vy);
+        (dot + dx * dvx + dy * dvy, speed + dvx * dvx + dvy * dvy)
+    });
+
+    if speed == 0.0 {
+        0
+    } else {
+        (-dot / speed).round().max(0.0) as i64
+    }
+}
+
+// The second at which the lights' bounding box is smallest, refining the
+// closed-form estimate by walking downhill.
+pub fn convergence(lights: &[Light]) -> i64 {
+    let mut seconds = estimate(lights);
+    loop {
+        if seconds > 0 && area(lights, seconds - 1) < area(lights, seconds) {
+            seconds -= 1;
+        } else if area(lights, seconds + 1) < area(lights, seconds) {
+            seconds += 1;
+        } else {
+            return seconds;
+        }
+    }
+}
+
+pub fn render(points: &[Point]) -> String {
+    let (min, max) = match bounds(points) {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+    let lit: HashSet<&Point> = points.iter().collect();
+
+    (min.y()..=max.y())
+        .map(|y| {
+            (min.x()..=max.x())
+                .map(|x| {
+                    if lit.contains(&Point::new(x, y)) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+const GLYPH_WIDTH: usize = 6;
+const GLYPH_HEIGHT: usize = 10;
+// Glyphs are separated by two blank columns.
+const GLYPH_PITCH: usize = 8;
+
+// The letters of the puzzle's 6x10 font that have been seen in messages.
+#[rustfmt::skip]
+static FONT: [(char, [&str; GLYPH_HEIGHT]); 15] = [
+    ('A', ["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', ["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', [".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', [".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', ["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', ["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', ["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', ["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', ["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', ["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', ["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', ["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', ["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+// Reads a rendered message one glyph at a time, if every glyph is in the
+// font.
+pub fn recognise(picture: &str) -> Option<String> {
+    let rows: Vec<&[u8]> = picture.lines().map(|l| l.as_bytes()).collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return None;
+    }
+
+    let width = rows[0].len();
+    (0..(width + GLYPH_PITCH - GLYPH_WIDTH) / GLYPH_PITCH)
+        .map(|i| {
+            let left = i * GLYPH_PITCH;
+            let glyph = |row: &[u8]| -> Vec<u8> {
+                (left..left + GLYPH_WIDTH)
+                    .map(|x| row.get(x).cloned().unwrap_or(b'.'))
+                    .collect()
+            };
+
+            FONT.iter()
+                .find(|(_, font)| {
+                    rows.iter()
+                        .zip(font.iter())
+                        .all(|(row, line)| glyph(row) == line.as_bytes())
+                })
+                .map(|&(c, _)| c)
+        })
+        .collect()
+}
+
+// The message as text, or as a picture when the font does not cover it.
+#[aoc(day10, part1)]
+fn answer_1(lights: &[Light]) -> String {
+    let picture = render(&positions(lights, convergence(lights)));
+    recognise(&picture).unwrap_or_else(|| format!("\n{}", picture))
+}
+
+#[aoc(day10, part2)]
+fn answer_2(lights: &[Light]) -> i64 {
+    convergence(lights)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_INPUT: &'static str = "\
+position=< 9,  1> velocity=< 0,  2>
+position=< 7,  0> velocity=<-1,  0>
+position=< 3, -2> velocity=<-1,  1>
+position=< 6, 10> velocity=<-2, -1>
+position=< 2, -4> velocity=< 2,  2>
+position=<-6, 10> velocity=< 2, -2>
+position=< 1,  8> velocity=< 1, -1>
+position=< 1,  7> velocity=< 1,  0>
+position=<-3, 11> velocity=< 1, -2>
+position=< 7,  6> velocity=<-1, -1>
+position=<-2,  3> velocity=< 1,  0>
+position=<-4,  3> velocity=< 2,  0>
+position=<10, -3> velocity=<-1,  1>
+position=< 5, 11> velocity=< 1, -2>
+position=< 4,  7> velocity=< 0, -1>
+position=< 8, -2> velocity=< 0,  1>
+position=<15,  0> velocity=<-2,  0>
+position=< 1,  6> velocity=< 1,  0>
+position=< 8,  9> velocity=< 0, -1>
+position=< 3,  3> velocity=<-1,  1>
+position=< 0,  5> velocity=< 0, -1>
+position=<-2,  2> velocity=< 2,  0>
+position=< 5, -2> velocity=< 1,  2>
+position=< 1,  4> velocity=< 2,  1>
+position=<-2,  7> velocity=< 2, -2>
+position=< 3,  6> velocity=<-1, -1>
+position=< 5,  0> velocity=< 1,  0>
+position=<-6,  0> velocity=< 2,  0>
+position=< 5,  9> velocity=< 1, -2>
+position=<14,  7> velocity=<-2,  0>
+position=<-3,  6> velocity=< 2, -1>";
+
+    // Lights spelling `text` in the font after `seconds`.
+    fn spelling(text: &str, seconds: i64) -> Vec<Light> {
+        let mut lights = Vec::new();
+        for (i, c) in text.chars().enumerate() {
+            let (_, glyph) = FONT.iter().find(|&&(f, _)| f == c).unwrap();
+            for (y, row) in glyph.iter().enumerate() {
+                for (x, _) in row.chars().enumerate().filter(|&(_, p)| p == '#') {
+                    let n = lights.len() as i64;
+                    let velocity = Point::new(n % 11 - 5, (n * 7) % 11 - 5);
+                    let x = (i * GLYPH_PITCH + x) as i64 - velocity.x() * seconds;
+                    let y = y as i64 - velocity.y() * seconds;
+                    lights.push(Light {
+                        position: Point::new(x, y),
+                        velocity,
+                    });
+                }
+            }
+        }
+
+        lights
+    }
+
+    #[test]
+    fn parser() {
+        assert_eq!(
+            Light {
+                position: Point::new(-6, 10),
+                velocity: Point::new(2, -2),
+            },
+            input_generator(TEST_INPUT).unwrap()[5]
+        );
+        assert_eq!(
+            Err(ParseError {
+                line: 2,
+                text: "position=<1, 2>".to_string(),
+            }),
+            input_generator("position=<0, 0> velocity=<1, 1>\nposition=<1, 2>")
+        );
+    }
+
+    #[test]
+    fn examples_1() {
+        let lights = input_generator(TEST_INPUT).unwrap();
+
+        assert_eq!(
+            "\
+#...#..###
+#...#...#.
+#...#...#.
+#####...#.
+#...#...#.
+#...#...#.
+#...#...#.
+#...#..###",
+            render(&positions(&lights, 3))
+        );
+        assert_eq!(None, recognise(&render(&positions(&lights, 3))));
+    }
+
+    #[test]
+    fn examples_2() {
+        assert_eq!(3, answer_2(&input_generator(TEST_INPUT).unwrap()));
+    }
+
+    #[test]
+    fn recognises_messages() {
+        let lights = spelling("ABCEFGHJKLNPRXZ", 10_391);
+
+        assert_eq!(10_391, convergence(&lights));
+        assert_eq!("ABCEFGHJKLNPRXZ", answer_1(&lights));
+    }
+}
@@ -0,0 +1,88 @@
+use summed_area::SummedAreaTable;
+
+const GRID_SIZE: usize = 300;
+
+#[aoc_generator(day11)]
+pub fn input_generator(input: &str) -> Result<i64, std::num::ParseIntError> {
+    input.trim().parse()
+}
+
+// The power level of the fuel cell at `x`, `y`, counting from 1.
+pub fn power_level(x: i64, y: i64, serial: i64) -> i64 {
+    let rack = x + 10;
+    (rack * y + serial) * rack / 100 % 10 - 5
+}
+
+pub fn power_grid(serial: i64) -> SummedAreaTable {
+    SummedAreaTable::new(GRID_SIZE, GRID_SIZE, |x, y| {
+        power_level(x as i64 + 1, y as i64 + 1, serial)
+    })
+}
+
+// The top left corner and total power of the most powerful square of the
+// given size, the first in reading order among equals.
+pub fn best_square(grid: &SummedAreaTable, size: usize) -> Option<(usize, usize, i64)> {
+    let mut best: Option<(usize, usize, i64)> = None;
+    for y in 0..(grid.height() + 1).saturating_sub(size) {
+        for x in 0..(grid.width() + 1).saturating_sub(size) {
+            let power = grid.sum(x, y, size, size);
+            if best.is_none_or(|(_, _, most)| power > most) {
+                best = Some((x + 1, y + 1, power));
+            }
+        }
+    }
+
+    best
+}
+
+// The top left corner, size and total power of the most powerful square of
+// any size, the smallest among equals.
+pub fn best_square_of_any_size(grid: &SummedAreaTable) -> Option<(usize, usize, usize, i64)> {
+    (1..=grid.width().min(grid.height()))
+        .filter_map(|size| best_square(grid, size).map(|(x, y, power)| (x, y, size, power)))
+        .fold(None, |best, square| match best {
+            Some(best) if best.3 >= square.3 => Some(best),
+            _ => Some(square),
+        })
+}
+
+#[aoc(day11, part1)]
+fn answer_1(serial: &i64) -> String {
+    let (x, y, _) = best_square(&power_grid(*serial), 3).unwrap();
+    format!("{},{}", x, y)
+}
+
+#[aoc(day11, part2)]
+fn answer_2(serial: &i64) -> String {
+    let (x, y, size, _) = best_square_of_any_size(&power_grid(*serial)).unwrap();
+    format!("{},{},{}", x, y, size)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn power_levels() {
+        assert_eq!(4, power_level(3, 5, 8));
+        assert_eq!(-5, power_level(122, 79, 57));
+        assert_eq!(0, power_level(217, 196, 39));
+        assert_eq!(4, power_level(101, 153, 71));
+    }
+
+    #[test]
+    fn examples_1() {
+        assert_eq!(Some((33, 45, 29)), best_square(&power_grid(18), 3));
+        assert_eq!(Some((21, 61, 30)), best_square(&power_grid(42), 3));
+        assert_eq!("33,45", answer_1(&input_generator("18\n").unwrap()));
+    }
+
+    #[test]
+    fn examples_2() {
+        assert_eq!(
+            Some((90, 269, 16, 113)),
+            best_square_of_any_size(&power_grid(18))
+        );
+        assert_eq!("232,251,12", answer_2(&input_generator("42").unwrap()));
+    }
+}
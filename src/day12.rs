@@ -0,0 +1,280 @@
+use regex::Regex;
+
+// Whether a pot has a plant next generation, indexed by the five pots
+// around it read as bits, most significant on the left.
+pub type Rules = [bool; 32];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    pub initial: Pots,
+    pub rules: Rules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    Parse { line: usize, text: String },
+    // A rule growing plants in empty pots would fill the infinite row.
+    InfiniteGrowth,
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InputError::Parse { line, text } => write!(f, "invalid line {}: {:?}", line, text),
+            InputError::InfiniteGrowth => write!(f, "plants grow in empty pots"),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+// The pots from the first plant to the last, with the number of the first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pots {
+    pub first: i64,
+    pub plants: Vec<bool>,
+}
+
+impl Pots {
+    fn new(first: i64, plants: Vec<bool>) -> Self {
+        match plants.iter().position(|&p| p) {
+            Some(start) => {
+                let end = plants.iter().rposition(|&p| p).unwrap() + 1;
+                Pots {
+                    first: first + start as i64,
+                    plants: plants[start..end].to_vec(),
+                }
+            }
+            None => Pots {
+                first: 0,
+                plants: Vec::new(),
+            },
+        }
+    }
+
+    // The next generation. Only pots within two of a plant can change, so the
+    // window grows by two on each side and slides over the row as bits.
+    pub fn step(&self, rules: &Rules) -> Pots {
+        let len = self.plants.len();
+        let mut window = 0;
+        let plants = (0..len + 4)
+            .map(|i| {
+                let incoming = i < len && self.plants[i];
+                window = (window << 1 | incoming as usize) & 0b11111;
+                rules[window]
+            })
+            .collect();
+
+        Pots::new(self.first - 2, plants)
+    }
+
+    pub fn sum(&self) -> i64 {
+        self.plants
+            .iter()
+            .enumerate()
+            .filter(|(_, &p)| p)
+            .map(|(i, _)| self.first + i as i64)
+            .sum()
+    }
+}
+
+impl std::fmt::Display for Pots {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let row: String = self
+            .plants
+            .iter()
+            .map(|&p| if p { '#' } else { '.' })
+            .collect();
+        write!(f, "{}", row)
+    }
+}
+
+fn plants(s: &str) -> Vec<bool> {
+    s.chars().map(|c| c == '#').collect()
+}
+
+#[aoc_generator(day12)]
+pub fn input_generator(input: &str) -> Result<Input, InputError> {
+    lazy_static! {
+        static ref INITIAL: Regex = Regex::new(r"^initial state: ([.#]*)$").unwrap();
+        static ref RULE: Regex = Regex::new(r"^([.#]{5}) => ([.#])$").unwrap();
+    }
+
+    let mut lines = input.trim().lines().enumerate();
+    let error = |(i, l): (usize, &str)| InputError::Parse {
+        line: i + 1,
+        text: l.to_string(),
+    };
+
+    let (i, l) = lines.next().unwrap_or((0, ""));
+    let initial = INITIAL
+        .captures(l.trim())
+        .map(|c| Pots::new(0, plants(&c[1])))
+        .ok_or_else(|| error((i, l)))?;
+
+    let mut rules = [false; 32];
+    for (i, l) in lines.filter(|(_, l)| !l.trim().is_empty()) {
+        let c = RULE.captures(l.trim()).ok_or_else(|| error((i, l)))?;
+        let pattern = plants(&c[1])
+            .into_iter()
+            .fold(0, |bits, p| bits << 1 | p as usize);
+        rules[pattern] = &c[2] == "#";
+    }
+
+    if rules[0] {
+        return Err(InputError::InfiniteGrowth);
+    }
+
+    Ok(Input { initial, rules })
+}
+
+// The pots after `generations`. Once the plants repeat an earlier
+// generation, moved along the row or not, they keep repeating with the same
+// period and shift, so whole periods are skipped. Repeats are found with
+// Brent's algorithm, which only keeps one earlier generation to compare
+// with, so rules that never settle cost no extra memory.
+pub fn simulate(input: &Input, generations: u64) -> Pots {
+    let mut pots = input.initial.clone();
+    let mut saved = (0, pots.clone());
+    let mut power = 1;
+    let mut generation = 0;
+
+    while generation < generations {
+        pots = pots.step(&input.rules);
+        generation += 1;
+
+        if pots.plants == saved.1.plants {
+            let period = generation - saved.0;
+            let periods = (generations - generation) / period;
+            pots.first += (pots.first - saved.1.first) * periods as i64;
+            for _ in generation + periods * period..generations {
+                pots = pots.step(&input.rules);
+            }
+
+            return pots;
+        }
+
+        if generation - saved.0 == power {
+            saved = (generation, pots.clone());
+            power *= 2;
+        }
+    }
+
+    pots
+}
+
+#[aoc(day12, part1)]
+fn answer_1(input: &Input) -> i64 {
+    simulate(input, 20).sum()
+}
+
+#[aoc(day12, part2)]
+fn answer_2(input: &Input) -> i64 {
+    simulate(input, 50_000_000_000).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_INPUT: &'static str = "\
+initial state: #..#.#..##......###...###
+
+...## => #
+..#.. => #
+.#... => #
+.#.#. => #
+.#.## => #
+.##.. => #
+.#### => #
+#.#.# => #
+#.### => #
+##.#. => #
+##.## => #
+###.. => #
+###.# => #
+####. => #";
+
+    #[test]
+    fn parser() {
+        let input = input_generator(TEST_INPUT).unwrap();
+
+        assert_eq!("#..#.#..##......###...###", input.initial.to_string());
+        assert_eq!(14, input.rules.iter().filter(|&&r| r).count());
+        assert!(input.rules[0b00011]);
+        assert!(!input.rules[0b00001]);
+        assert_eq!(
+            Err(InputError::Parse {
+                line: 3,
+                text: "...# => #".to_string(),
+            }),
+            input_generator("initial state: #\n\n...# => #")
+        );
+        assert_eq!(
+            Err(InputError::InfiniteGrowth),
+            input_generator("initial state: #\n\n..... => #")
+        );
+    }
+
+    #[test]
+    fn generations() {
+        let input = input_generator(TEST_INPUT).unwrap();
+        let pots = input.initial.step(&input.rules);
+        assert_eq!(
+            (0, "#...#....#.....#..#..#..#".to_string()),
+            (pots.first, pots.to_string())
+        );
+
+        let pots = simulate(&input, 20);
+        assert_eq!(-2, pots.first);
+        assert_eq!("#....##....#####...#######....#.#..##", pots.to_string());
+    }
+
+    #[test]
+    fn extrapolation_matches_simulation() {
+        let input = input_generator(TEST_INPUT).unwrap();
+        let mut pots = input.initial.clone();
+        for _ in 0..500 {
+            pots = pots.step(&input.rules);
+        }
+
+        assert_eq!(pots, simulate(&input, 500));
+    }
+
+    #[test]
+    fn oscillations_are_extrapolated() {
+        // Alternates between two shapes, moving two pots left every other
+        // generation.
+        let input = input_generator(
+            "initial state: ##..#\n\n...#. => #\n...## => #\n..#.. => #\n#..## => #",
+        )
+        .unwrap();
+        let mut pots = input.initial.clone();
+        for generation in 0..1000 {
+            assert_eq!(pots, simulate(&input, generation));
+            pots = pots.step(&input.rules);
+        }
+
+        let pots = simulate(&input, 10);
+        assert_eq!(
+            Pots {
+                first: pots.first - (50_000_000_000 - 10),
+                plants: pots.plants.clone(),
+            },
+            simulate(&input, 50_000_000_000)
+        );
+    }
+
+    #[test]
+    fn examples_1() {
+        assert_eq!(325, answer_1(&input_generator(TEST_INPUT).unwrap()));
+    }
+
+    #[test]
+    fn examples_2() {
+        assert_eq!(
+            999999999374,
+            answer_2(&input_generator(TEST_INPUT).unwrap())
+        );
+    }
+}
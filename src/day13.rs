@@ -0,0 +1,500 @@
+use point::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Track {
+    Empty,
+    Vertical,
+    Horizontal,
+    // `/`
+    Slash,
+    // `\`
+    Backslash,
+    Intersection,
+}
+
+impl Track {
+    fn symbol(self) -> char {
+        match self {
+            Track::Empty => ' ',
+            Track::Vertical => '|',
+            Track::Horizontal => '-',
+            Track::Slash => '/',
+            Track::Backslash => '\\',
+            Track::Intersection => '+',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heading {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Heading {
+    fn left(self) -> Heading {
+        match self {
+            Heading::Up => Heading::Left,
+            Heading::Left => Heading::Down,
+            Heading::Down => Heading::Right,
+            Heading::Right => Heading::Up,
+        }
+    }
+
+    fn right(self) -> Heading {
+        self.left().left().left()
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Heading::Up => '^',
+            Heading::Down => 'v',
+            Heading::Left => '<',
+            Heading::Right => '>',
+        }
+    }
+}
+
+// What a cart does at its next intersection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Left,
+    Straight,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cart {
+    pub position: Point,
+    pub heading: Heading,
+    pub next_turn: Turn,
+}
+
+impl Cart {
+    fn advance(&mut self, tracks: &[Vec<Track>]) {
+        let (x, y) = (self.position.x(), self.position.y());
+        self.position = match self.heading {
+            Heading::Up => Point::new(x, y - 1),
+            Heading::Down => Point::new(x, y + 1),
+            Heading::Left => Point::new(x - 1, y),
+            Heading::Right => Point::new(x + 1, y),
+        };
+
+        self.heading = match (track_at(tracks, &self.position), self.heading) {
+            (Track::Slash, Heading::Up) | (Track::Slash, Heading::Down) => self.heading.right(),
+            (Track::Slash, _) => self.heading.left(),
+            (Track::Backslash, Heading::Up) | (Track::Backslash, Heading::Down) => {
+                self.heading.left()
+            }
+            (Track::Backslash, _) => self.heading.right(),
+            (Track::Intersection, heading) => {
+                let (heading, next_turn) = match self.next_turn {
+                    Turn::Left => (heading.left(), Turn::Straight),
+                    Turn::Straight => (heading, Turn::Right),
+                    Turn::Right => (heading.right(), Turn::Left),
+                };
+                self.next_turn = next_turn;
+                heading
+            }
+            (Track::Empty, _) => panic!("cart left the track at {:?}", self.position),
+            (_, heading) => heading,
+        };
+    }
+}
+
+fn track_at(tracks: &[Vec<Track>], p: &Point) -> Track {
+    if p.x() < 0 || p.y() < 0 {
+        return Track::Empty;
+    }
+
+    tracks
+        .get(p.y() as usize)
+        .and_then(|row| row.get(p.x() as usize))
+        .cloned()
+        .unwrap_or(Track::Empty)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnknownSymbol,
+    // The track around a cart doesn't fit any piece it could be on.
+    NoTrackUnderCart,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub symbol: char,
+    pub kind: ErrorKind,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let problem = match self.kind {
+            ErrorKind::UnknownSymbol => "unknown symbol",
+            ErrorKind::NoTrackUnderCart => "no track fits under cart",
+        };
+        write!(
+            f,
+            "{} {:?} on line {}, column {}",
+            problem, self.symbol, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mine {
+    pub tracks: Vec<Vec<Track>>,
+    pub carts: Vec<Cart>,
+    pub ticks: usize,
+    // Where carts crashed during the last tick.
+    pub crashes: Vec<Point>,
+}
+
+#[aoc_generator(day13)]
+pub fn input_generator(input: &str) -> Result<Mine, ParseError> {
+    let lines: Vec<&[u8]> = input
+        .trim_end()
+        .lines()
+        .map(|l| l.trim_end_matches('\r').as_bytes())
+        .collect();
+    let symbol = |x: usize, y: usize| lines.get(y).and_then(|l| l.get(x)).cloned();
+
+    let mut tracks = Vec::new();
+    let mut carts = Vec::new();
+    for (y, line) in lines.iter().enumerate() {
+        let mut row = Vec::new();
+        for (x, &c) in line.iter().enumerate() {
+            let error = |kind| ParseError {
+                line: y + 1,
+                column: x + 1,
+                symbol: c as char,
+                kind,
+            };
+            let heading = match c {
+                b'^' => Some(Heading::Up),
+                b'v' => Some(Heading::Down),
+                b'<' => Some(Heading::Left),
+                b'>' => Some(Heading::Right),
+                _ => None,
+            };
+
+            row.push(match (c, heading) {
+                (b' ', _) => Track::Empty,
+                (b'|', _) => Track::Vertical,
+                (b'-', _) => Track::Horizontal,
+                (b'/', _) => Track::Slash,
+                (b'\\', _) => Track::Backslash,
+                (b'+', _) => Track::Intersection,
+                (_, Some(heading)) => {
+                    carts.push(Cart {
+                        position: Point::new(x as i64, y as i64),
+                        heading,
+                        next_turn: Turn::Left,
+                    });
+
+                    match hidden_track(&symbol, x as i64, y as i64, heading) {
+                        Some(track) => track,
+                        None => return Err(error(ErrorKind::NoTrackUnderCart)),
+                    }
+                }
+                (_, None) => return Err(error(ErrorKind::UnknownSymbol)),
+            });
+        }
+        tracks.push(row);
+    }
+
+    Ok(Mine {
+        tracks,
+        carts,
+        ticks: 0,
+        crashes: Vec::new(),
+    })
+}
+
+// The piece a cart hides, worked out from which neighbours lead into it.
+// Each curve symbol is one of two corners, so a curve beside the cart only
+// leads into it if track also leaves the curve on the corner's other side.
+// The cart must be able to move on along its heading.
+fn hidden_track<F>(symbol: &F, x: i64, y: i64, heading: Heading) -> Option<Track>
+where
+    F: Fn(usize, usize) -> Option<u8>,
+{
+    let at = |x: i64, y: i64| {
+        if x < 0 || y < 0 {
+            None
+        } else {
+            symbol(x as usize, y as usize)
+        }
+    };
+    let is = |x: i64, y: i64, symbols: &[u8]| at(x, y).is_some_and(|s| symbols.contains(&s));
+    let (vertical, horizontal): (&[u8], &[u8]) = (b"|+^v", b"-+<>");
+
+    let up = is(x, y - 1, vertical)
+        || is(x, y - 1, b"/") && is(x + 1, y - 1, horizontal)
+        || is(x, y - 1, b"\\") && is(x - 1, y - 1, horizontal);
+    let down = is(x, y + 1, vertical)
+        || is(x, y + 1, b"/") && is(x - 1, y + 1, horizontal)
+        || is(x, y + 1, b"\\") && is(x + 1, y + 1, horizontal);
+    let left = is(x - 1, y, horizontal)
+        || is(x - 1, y, b"/") && is(x - 1, y + 1, vertical)
+        || is(x - 1, y, b"\\") && is(x - 1, y - 1, vertical);
+    let right = is(x + 1, y, horizontal)
+        || is(x + 1, y, b"/") && is(x + 1, y - 1, vertical)
+        || is(x + 1, y, b"\\") && is(x + 1, y + 1, vertical);
+
+    let track = match (up, down, left, right) {
+        (true, true, true, true) => Track::Intersection,
+        (true, true, false, false) => Track::Vertical,
+        (false, false, true, true) => Track::Horizontal,
+        (false, true, false, true) | (true, false, true, false) => Track::Slash,
+        (false, true, true, false) | (true, false, false, true) => Track::Backslash,
+        _ => return None,
+    };
+    let ahead = match heading {
+        Heading::Up => up,
+        Heading::Down => down,
+        Heading::Left => left,
+        Heading::Right => right,
+    };
+
+    Some(track).filter(|_| ahead)
+}
+
+impl Mine {
+    // Moves every cart once, in reading order. Carts that crash are removed
+    // straight away, and where they crashed is returned.
+    pub fn tick(&mut self) -> Vec<Point> {
+        self.carts.sort_by_key(|c| (c.position.y(), c.position.x()));
+
+        let mut crashed = vec![false; self.carts.len()];
+        let mut crashes = Vec::new();
+        for i in 0..self.carts.len() {
+            if crashed[i] {
+                continue;
+            }
+
+            self.carts[i].advance(&self.tracks);
+            let position = &self.carts[i].position;
+            let other = (0..self.carts.len())
+                .find(|&j| j != i && !crashed[j] && self.carts[j].position == *position);
+            if let Some(j) = other {
+                crashed[i] = true;
+                crashed[j] = true;
+                crashes.push(position.clone());
+            }
+        }
+
+        let mut crashed = crashed.into_iter();
+        self.carts.retain(|_| !crashed.next().unwrap());
+        self.ticks += 1;
+        self.crashes = crashes.clone();
+
+        crashes
+    }
+
+    // Where the first crash happens, if ever.
+    pub fn first_crash(&mut self) -> Option<Point> {
+        while self.carts.len() > 1 {
+            if let Some(crash) = self.tick().into_iter().next() {
+                return Some(crash);
+            }
+        }
+
+        None
+    }
+
+    // Where the last cart is at the end of the tick that leaves only it.
+    pub fn last_cart(&mut self) -> Option<Point> {
+        while self.carts.len() > 1 {
+            self.tick();
+        }
+
+        self.carts.first().map(|c| c.position.clone())
+    }
+
+    // The tracks with carts drawn over them, and an `X` wherever carts
+    // crashed during the last tick. Trailing spaces are left off.
+    pub fn render(&self) -> String {
+        let mut rows: Vec<Vec<char>> = self
+            .tracks
+            .iter()
+            .map(|row| row.iter().map(|t| t.symbol()).collect())
+            .collect();
+
+        let marks = self
+            .carts
+            .iter()
+            .map(|c| (&c.position, c.heading.symbol()))
+            .chain(self.crashes.iter().map(|p| (p, 'X')));
+        for (p, symbol) in marks {
+            rows[p.y() as usize][p.x() as usize] = symbol;
+        }
+
+        rows.into_iter()
+            .map(|row| row.into_iter().collect::<String>().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// The mine as it is after `ticks` ticks.
+pub fn frame(mine: &Mine, ticks: usize) -> String {
+    let mut mine = mine.clone();
+    for _ in 0..ticks {
+        mine.tick();
+    }
+
+    mine.render()
+}
+
+fn coordinates(p: &Point) -> String {
+    format!("{},{}", p.x(), p.y())
+}
+
+#[aoc(day13, part1)]
+fn answer_1(mine: &Mine) -> String {
+    mine.clone()
+        .first_crash()
+        .map_or_else(|| "no crash".to_string(), |p| coordinates(&p))
+}
+
+#[aoc(day13, part2)]
+fn answer_2(mine: &Mine) -> String {
+    mine.clone()
+        .last_cart()
+        .map_or_else(|| "no cart left".to_string(), |p| coordinates(&p))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_INPUT_1: &'static str = r"/->-\
+|   |  /----\
+| /-+--+-\  |
+| | |  | v  |
+\-+-/  \-+--/
+  \------/";
+
+    const TEST_INPUT_2: &'static str = r"/>-<\
+|   |
+| /<+-\
+| | | v
+\>+</ |
+  |   ^
+  \<->/";
+
+    #[test]
+    fn parser() {
+        let mine = input_generator(TEST_INPUT_1).unwrap();
+
+        assert_eq!(
+            vec![
+                Cart {
+                    position: Point::new(2, 0),
+                    heading: Heading::Right,
+                    next_turn: Turn::Left,
+                },
+                Cart {
+                    position: Point::new(9, 3),
+                    heading: Heading::Down,
+                    next_turn: Turn::Left,
+                },
+            ],
+            mine.carts
+        );
+        assert_eq!(Track::Horizontal, mine.tracks[0][2]);
+        assert_eq!(Track::Vertical, mine.tracks[3][9]);
+        assert_eq!(TEST_INPUT_1, mine.render());
+        assert_eq!(
+            Err(ParseError {
+                line: 2,
+                column: 3,
+                symbol: '#',
+                kind: ErrorKind::UnknownSymbol,
+            }),
+            input_generator("-->-\n--#-")
+        );
+        assert_eq!(
+            Err(ParseError {
+                line: 2,
+                column: 2,
+                symbol: '>',
+                kind: ErrorKind::NoTrackUnderCart,
+            }),
+            input_generator(" | \n->-")
+        );
+        assert_eq!(
+            Err(ErrorKind::NoTrackUnderCart),
+            input_generator("/---\\\n|   |\n\\--->").map_err(|e| e.kind)
+        );
+    }
+
+    #[test]
+    fn carts_hide_intersections() {
+        let mine = input_generator(" | \n->-\n | ").unwrap();
+        assert_eq!(Track::Intersection, mine.tracks[1][1]);
+
+        let mine = input_generator(" | \n-^-\n | ").unwrap();
+        assert_eq!(Track::Intersection, mine.tracks[1][1]);
+    }
+
+    #[test]
+    fn carts_hide_curves() {
+        for (input, track) in [
+            (">---\\\n|   |\n\\---/", Track::Slash),
+            ("/---v\n|   |\n\\---/", Track::Backslash),
+            ("/---\\\n|   |\n^---/", Track::Backslash),
+            ("/---\\\n|   |\n\\---<", Track::Slash),
+        ] {
+            let mine = input_generator(input).unwrap();
+            let cart = &mine.carts[0].position;
+            assert_eq!(track, mine.tracks[cart.y() as usize][cart.x() as usize]);
+
+            // Twelve ticks take the cart once round the loop.
+            assert_eq!(input, frame(&mine, 12));
+            assert_eq!(input, frame(&mine, 36));
+        }
+    }
+
+    #[test]
+    fn curves_beside_carts() {
+        let mine = input_generator(" /-\\\n->-+-\n | |\n \\-/").unwrap();
+        assert_eq!(Track::Intersection, mine.tracks[1][1]);
+
+        // This curve turns away from the cart rather than into it.
+        let mine = input_generator("-/\n->-").unwrap();
+        assert_eq!(Track::Horizontal, mine.tracks[1][1]);
+    }
+
+    #[test]
+    fn frames() {
+        let mine = input_generator(TEST_INPUT_1).unwrap();
+
+        assert_eq!(
+            r"/---\
+|   |  /----\
+| /-+--+-\  |
+| | |  X |  |
+\-+-/  \-+--/
+  \------/",
+            frame(&mine, 14)
+        );
+    }
+
+    #[test]
+    fn examples_1() {
+        assert_eq!("7,3", answer_1(&input_generator(TEST_INPUT_1).unwrap()));
+    }
+
+    #[test]
+    fn examples_2() {
+        assert_eq!("6,4", answer_2(&input_generator(TEST_INPUT_2).unwrap()));
+    }
+}
@@ -0,0 +1,170 @@
+// The recipe scores so far, one digit each, and the recipes the two elves
+// are on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoreboard {
+    scores: Vec<u8>,
+    elves: [usize; 2],
+}
+
+impl Default for Scoreboard {
+    fn default() -> Self {
+        Scoreboard {
+            scores: vec![3, 7],
+            elves: [0, 1],
+        }
+    }
+}
+
+impl Scoreboard {
+    pub fn new() -> Self {
+        Scoreboard::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.scores.reserve(capacity);
+        scoreboard
+    }
+
+    pub fn scores(&self) -> &[u8] {
+        &self.scores
+    }
+
+    // Makes the next recipes and moves the elves on, returning how many
+    // recipes were added.
+    pub fn step(&mut self) -> usize {
+        let [a, b] = self.elves;
+        let sum = self.scores[a] + self.scores[b];
+        let added = if sum >= 10 {
+            self.scores.extend_from_slice(&[1, sum - 10]);
+            2
+        } else {
+            self.scores.push(sum);
+            1
+        };
+
+        let len = self.scores.len();
+        self.elves = [
+            (a + 1 + self.scores[a] as usize) % len,
+            (b + 1 + self.scores[b] as usize) % len,
+        ];
+
+        added
+    }
+}
+
+// The ten scores after the first `recipes`.
+pub fn ten_after(recipes: usize) -> Vec<u8> {
+    let mut scoreboard = Scoreboard::with_capacity(recipes + 11);
+    while scoreboard.scores().len() < recipes + 10 {
+        scoreboard.step();
+    }
+
+    scoreboard.scores()[recipes..recipes + 10].to_vec()
+}
+
+// How many recipes come before `pattern` first appears. Only the windows
+// ending on newly added recipes can be new matches, so nothing is rescanned.
+// An empty pattern is found before any recipes. There is no limit on the
+// search, so a pattern that never turns up keeps it going forever.
+pub fn recipes_before(pattern: &[u8]) -> usize {
+    if pattern.is_empty() {
+        return 0;
+    }
+
+    let mut scoreboard = Scoreboard::new();
+    let mut added = scoreboard.scores().len();
+    loop {
+        let scores = scoreboard.scores();
+        for end in scores.len() - added + 1..=scores.len() {
+            if end >= pattern.len() && &scores[end - pattern.len()..end] == pattern {
+                return end - pattern.len();
+            }
+        }
+
+        added = scoreboard.step();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputError(pub String);
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "expected a string of digits, got {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InputError {}
+
+#[aoc_generator(day14)]
+pub fn input_generator(input: &str) -> Result<Vec<u8>, InputError> {
+    let input = input.trim();
+    if input.is_empty() || !input.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(InputError(input.to_string()));
+    }
+
+    Ok(input.bytes().map(|b| b - b'0').collect())
+}
+
+fn digits(scores: &[u8]) -> String {
+    scores.iter().map(|s| (b'0' + s) as char).collect()
+}
+
+#[aoc(day14, part1)]
+fn answer_1(input: &[u8]) -> String {
+    let recipes = input.iter().fold(0, |n, &d| n * 10 + d as usize);
+    digits(&ten_after(recipes))
+}
+
+#[aoc(day14, part2)]
+fn answer_2(input: &[u8]) -> usize {
+    recipes_before(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scoreboard() {
+        let mut scoreboard = Scoreboard::new();
+        assert_eq!(2, scoreboard.step());
+        assert_eq!(2, scoreboard.step());
+        assert_eq!(1, scoreboard.step());
+        assert_eq!(&[3, 7, 1, 0, 1, 0, 1], scoreboard.scores());
+    }
+
+    #[test]
+    fn parser() {
+        assert_eq!(Ok(vec![0, 1, 2, 4, 5]), input_generator("01245\n"));
+        assert_eq!(Err(InputError("12a".to_string())), input_generator("12a"));
+    }
+
+    #[test]
+    fn examples_1() {
+        assert_eq!("5158916779", answer_1(&input_generator("9").unwrap()));
+        assert_eq!("0124515891", answer_1(&input_generator("5").unwrap()));
+        assert_eq!("9251071085", answer_1(&input_generator("18").unwrap()));
+        assert_eq!("5941429882", answer_1(&input_generator("2018").unwrap()));
+    }
+
+    #[test]
+    fn examples_2() {
+        assert_eq!(9, answer_2(&input_generator("51589").unwrap()));
+        assert_eq!(5, answer_2(&input_generator("01245").unwrap()));
+        assert_eq!(18, answer_2(&input_generator("92510").unwrap()));
+        assert_eq!(2018, answer_2(&input_generator("59414").unwrap()));
+    }
+
+    #[test]
+    fn pattern_in_initial_scores() {
+        assert_eq!(0, recipes_before(&[3, 7]));
+        assert_eq!(1, recipes_before(&[7, 1, 0]));
+    }
+
+    #[test]
+    fn empty_pattern() {
+        assert_eq!(0, recipes_before(&[]));
+    }
+}
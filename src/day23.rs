@@ -1,6 +1,10 @@
 use coord::Coord;
-use failure::{format_err, Error};
+use rayon::prelude::*;
 use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::convert::TryFrom;
+use std::ops::Range;
 
 #[derive(PartialEq, Debug)]
 pub struct Nanobot {
@@ -10,7 +14,34 @@ pub struct Nanobot {
 
 impl Nanobot {
     fn in_range(&self, c: &Coord) -> bool {
-        self.pos.manhattan_distance(c) <= self.radius
+        self.pos.manhattan_distance(c) <= u128::from(self.radius)
+    }
+
+    // The smallest box containing the bot's range, cut down to the points a
+    // `Coord` can hold. Those are the only points ever asked about, and the
+    // cut doesn't change how far any of them are from the box.
+    fn bounds(&self) -> Bounds {
+        let r = self.radius;
+        let (x, y, z) = (self.pos.x(), self.pos.y(), self.pos.z());
+        Bounds {
+            min: Coord::new(
+                x.saturating_sub_unsigned(r),
+                y.saturating_sub_unsigned(r),
+                z.saturating_sub_unsigned(r),
+            ),
+            max: Coord::new(
+                x.saturating_add_unsigned(r),
+                y.saturating_add_unsigned(r),
+                z.saturating_add_unsigned(r),
+            ),
+        }
+    }
+
+    // Two ranges share a point when the bots are no further apart than the
+    // sum of their radii.
+    pub fn overlaps(&self, other: &Nanobot) -> bool {
+        self.pos.manhattan_distance(&other.pos)
+            <= u128::from(self.radius) + u128::from(other.radius)
     }
 }
 
@@ -20,94 +51,723 @@ impl AsRef<Nanobot> for Nanobot {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineError {
+    Format(String),
+    Number(String),
+}
+
+impl std::fmt::Display for LineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LineError::Format(s) => write!(f, "unrecognised nanobot {:?}", s),
+            LineError::Number(s) => write!(f, "number out of range: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for LineError {}
+
+// Every malformed line in the input, with its line number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub lines: Vec<(usize, LineError)>,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} malformed line(s)", self.lines.len())?;
+        for (line, error) in self.lines.iter() {
+            write!(f, "\n  line {}: {}", line, error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 impl std::str::FromStr for Nanobot {
-    type Err = Error;
+    type Err = LineError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         lazy_static! {
-            static ref re: Regex = Regex::new(r"pos=<(-?\d+),(-?\d+),(-?\d+)>, r=(\d+)").unwrap();
+            static ref RE: Regex = Regex::new(
+                r"^pos\s*=\s*<\s*([+-]?\d+)\s*,\s*([+-]?\d+)\s*,\s*([+-]?\d+)\s*>\s*,\s*r\s*=\s*(\+?\d+)$"
+            )
+            .unwrap();
         }
 
-        let c = re.captures(&s).ok_or(format_err!("error parsing {}", s))?;
+        let s = s.trim();
+        let c = RE
+            .captures(s)
+            .ok_or_else(|| LineError::Format(s.to_string()))?;
+        let coordinate = |s: &str| s.parse().map_err(|_| LineError::Number(s.to_string()));
 
         Ok(Nanobot {
-            pos: Coord::new(c[1].parse()?, c[2].parse()?, c[3].parse()?),
-            radius: c[4].parse()?,
+            pos: Coord::new(coordinate(&c[1])?, coordinate(&c[2])?, coordinate(&c[3])?),
+            radius: c[4]
+                .parse()
+                .map_err(|_| LineError::Number(c[4].to_string()))?,
         })
     }
 }
 
+// Blank lines are skipped.
 #[aoc_generator(day23)]
-pub fn input_generator(input: &str) -> Vec<Nanobot> {
-    input.lines().map(|l| l.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<Nanobot>, ParseError> {
+    let mut bots = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match line.parse() {
+            Ok(bot) => bots.push(bot),
+            Err(e) => errors.push((i + 1, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(bots)
+    } else {
+        Err(ParseError { lines: errors })
+    }
+}
+
+// An inclusive axis aligned box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Coord,
+    pub max: Coord,
+}
+
+impl Bounds {
+    fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: self.min.min(&other.min),
+            max: self.max.max(&other.max),
+        }
+    }
+
+    fn contains(&self, c: &Coord) -> bool {
+        self.distance(c) == 0
+    }
+
+    fn contains_bounds(&self, other: &Bounds) -> bool {
+        self.contains(&other.min) && self.contains(&other.max)
+    }
+
+    // Manhattan distance from `c` to the closest point in the box.
+    fn distance(&self, c: &Coord) -> u128 {
+        let axis = |p: i64, lo: i64, hi: i64| {
+            let (p, lo, hi) = (i128::from(p), i128::from(lo), i128::from(hi));
+            (lo - p).max(p - hi).max(0) as u128
+        };
+
+        axis(c.x(), self.min.x(), self.max.x())
+            + axis(c.y(), self.min.y(), self.max.y())
+            + axis(c.z(), self.min.z(), self.max.z())
+    }
+
+    // Manhattan distance from `c` to the furthest point in the box.
+    fn furthest(&self, c: &Coord) -> u128 {
+        let axis = |p: i64, lo: i64, hi: i64| u128::from(p.abs_diff(lo).max(p.abs_diff(hi)));
+
+        axis(c.x(), self.min.x(), self.max.x())
+            + axis(c.y(), self.min.y(), self.max.y())
+            + axis(c.z(), self.min.z(), self.max.z())
+    }
+}
+
+// A node of the field's bounding volume hierarchy, holding the bots in
+// `order[bots]`. `ranges` bounds their ranges and `positions` their
+// positions.
+struct Node {
+    ranges: Bounds,
+    positions: Bounds,
+    bots: Range<usize>,
+    children: Option<(usize, usize)>,
+}
+
+const LEAF_SIZE: usize = 8;
+
+// Bots indexed by a bounding volume hierarchy, so that range queries only
+// look at bots near the point or box asked about. Bots are identified by
+// their index in the slice the field was built from.
+pub struct NanobotField<'a> {
+    bots: &'a [Nanobot],
+    order: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+impl<'a> NanobotField<'a> {
+    pub fn new(bots: &'a [Nanobot]) -> Self {
+        let mut field = NanobotField {
+            bots,
+            order: (0..bots.len()).collect(),
+            nodes: Vec::new(),
+        };
+        if !bots.is_empty() {
+            field.build(0..bots.len());
+        }
+
+        field
+    }
+
+    // Adds the node for `order[range]`, split in half along the longest
+    // axis of its positions, and returns its index.
+    fn build(&mut self, range: Range<usize>) -> usize {
+        let bots = self.bots;
+        let members = &mut self.order[range.clone()];
+        let ranges = members
+            .iter()
+            .map(|&i| bots[i].bounds())
+            .reduce(|a, b| a.union(&b))
+            .unwrap();
+        let positions = members
+            .iter()
+            .map(|&i| Bounds {
+                min: bots[i].pos.clone(),
+                max: bots[i].pos.clone(),
+            })
+            .reduce(|a, b| a.union(&b))
+            .unwrap();
+
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            ranges,
+            positions,
+            bots: range.clone(),
+            children: None,
+        });
+        if range.len() <= LEAF_SIZE {
+            return node;
+        }
+
+        let extent = self.nodes[node].positions.max.clone();
+        let start = self.nodes[node].positions.min.clone();
+        let span = |axis: fn(&Coord) -> i64| axis(&extent).abs_diff(axis(&start));
+        let axis: fn(&Coord) -> i64 =
+            if span(Coord::x) >= span(Coord::y) && span(Coord::x) >= span(Coord::z) {
+                Coord::x
+            } else if span(Coord::y) >= span(Coord::z) {
+                Coord::y
+            } else {
+                Coord::z
+            };
+        members.sort_by_key(|&i| axis(&bots[i].pos));
+
+        let middle = range.start + range.len() / 2;
+        let left = self.build(range.start..middle);
+        let right = self.build(middle..range.end);
+        self.nodes[node].children = Some((left, right));
+
+        node
+    }
+
+    // Bots in nodes passing `descend` that also pass `keep`, in index order.
+    fn search<D, K>(&self, descend: D, keep: K) -> Vec<usize>
+    where
+        D: Fn(&Node) -> bool,
+        K: Fn(&Nanobot) -> bool,
+    {
+        let mut found = Vec::new();
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !descend(node) {
+                continue;
+            }
+
+            match node.children {
+                Some((left, right)) => stack.extend_from_slice(&[left, right]),
+                None => found.extend(
+                    self.order[node.bots.clone()]
+                        .iter()
+                        .filter(|&&i| keep(&self.bots[i])),
+                ),
+            }
+        }
+
+        found.sort();
+        found
+    }
+
+    // The bots whose range includes `c`.
+    pub fn covering(&self, c: &Coord) -> Vec<usize> {
+        self.search(|node| node.ranges.contains(c), |b| b.in_range(c))
+    }
+
+    pub fn count_covering(&self, c: &Coord) -> usize {
+        self.covering(c).len()
+    }
+
+    // `count_covering` for each of `points`, in parallel.
+    pub fn count_covering_all(&self, points: &[Coord]) -> Vec<usize> {
+        points.par_iter().map(|c| self.count_covering(c)).collect()
+    }
+
+    // The bots whose range includes every point in `bounds`.
+    pub fn covering_bounds(&self, bounds: &Bounds) -> Vec<usize> {
+        self.search(
+            |node| node.ranges.contains_bounds(bounds),
+            |b| bounds.furthest(&b.pos) <= u128::from(b.radius),
+        )
+    }
+
+    // The bots positioned within range of bot `bot`, including itself.
+    pub fn in_range_of(&self, bot: usize) -> Vec<usize> {
+        let bot = &self.bots[bot];
+        self.search(
+            |node| node.positions.distance(&bot.pos) <= u128::from(bot.radius),
+            |b| bot.in_range(&b.pos),
+        )
+    }
+
+    // The bot with the largest radius, the last one among equals.
+    pub fn strongest(&self) -> Option<usize> {
+        (0..self.bots.len()).max_by_key(|&i| self.bots[i].radius)
+    }
 }
 
 #[aoc(day23, part1)]
 fn answer_1(input: &[Nanobot]) -> usize {
-    let strongest = input
-        .iter()
-        .max_by(|&a, &b| a.radius.cmp(&b.radius))
-        .unwrap();
+    let field = NanobotField::new(input);
+    field
+        .strongest()
+        .map_or(0, |strongest| field.in_range_of(strongest).len())
+}
+
+// An axis aligned cube of `size` points along each axis, starting at `min`.
+// A cube may reach past the largest coordinate a `Coord` can hold, but only
+// the points it can hold are searched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cube {
+    min: Coord,
+    size: i128,
+}
+
+impl Cube {
+    fn bounds(&self) -> Bounds {
+        let max = |lo: i64| (i128::from(lo) + self.size - 1).min(i128::from(i64::MAX)) as i64;
+        Bounds {
+            min: self.min.clone(),
+            max: Coord::new(max(self.min.x()), max(self.min.y()), max(self.min.z())),
+        }
+    }
+
+    fn distance(&self, c: &Coord) -> u128 {
+        self.bounds().distance(c)
+    }
+
+    // The eighths of the cube that hold any points.
+    fn split(&self) -> Vec<Cube> {
+        let size = self.size / 2;
+        let offsets = |lo: i64| {
+            let upper = i64::try_from(i128::from(lo) + size).ok();
+            Some(lo).into_iter().chain(upper)
+        };
+
+        let mut cubes = Vec::with_capacity(8);
+        for x in offsets(self.min.x()) {
+            for y in offsets(self.min.y()) {
+                for z in offsets(self.min.z()) {
+                    cubes.push(Cube {
+                        min: Coord::new(x, y, z),
+                        size,
+                    });
+                }
+            }
+        }
+
+        cubes
+    }
+}
+
+// The smallest power-of-two cube covering every bot's range.
+fn bounding_cube(bots: &[Nanobot]) -> Cube {
+    let origin = Bounds {
+        min: Coord::default(),
+        max: Coord::default(),
+    };
+    let Bounds { min, max } = bots.iter().fold(origin, |a, b| a.union(&b.bounds()));
+
+    let span = |lo: i64, hi: i64| i128::from(hi) - i128::from(lo);
+    let extent = span(min.x(), max.x())
+        .max(span(min.y(), max.y()))
+        .max(span(min.z(), max.z()))
+        + 1;
+    let mut size = 1;
+    while size < extent {
+        size *= 2;
+    }
 
-    input.iter().filter(|n| strongest.in_range(&n.pos)).count()
+    Cube { min, size }
 }
 
-fn find_bounds<'a, I: Iterator<Item = &'a Coord>>(coords: I) -> (Coord, Coord) {
-    coords.fold((Coord::default(), Coord::default()), |(min, max), coord| {
-        (min.min(&coord), max.max(&coord))
-    })
+// Search priority of a cube: bots in range, distance from the reference,
+// size and minimum corner.
+type Bound = (usize, Reverse<u128>, i128, Reverse<(i64, i64, i64)>);
+
+// A point reached by the most bots, with its distance from the reference
+// point and the indices of the bots in range of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Optimum {
+    pub position: Coord,
+    pub distance: u128,
+    pub bots: Vec<usize>,
+}
+
+// Branch and bound over an octree of cubes. The number of bots reaching a
+// cube bounds how many can reach any point in it, and its distance to the
+// reference bounds how close any of its points are. Cubes are searched best
+// bound first, so the first single point reached is optimal. Larger cubes go
+// first among equal bounds, so that remaining ties go to the smallest
+// coordinate.
+//
+// Carrying on after the first point yields every other point in range of as
+// many bots, closest first.
+pub struct Optima<'a> {
+    bots: &'a [Nanobot],
+    reference: Coord,
+    queue: BinaryHeap<Bound>,
+    count: Option<usize>,
+}
+
+impl<'a> Optima<'a> {
+    fn push(&mut self, cube: &Cube) {
+        let in_range = self
+            .bots
+            .iter()
+            .filter(|b| cube.distance(&b.pos) <= u128::from(b.radius))
+            .count();
+
+        self.queue.push((
+            in_range,
+            Reverse(cube.distance(&self.reference)),
+            cube.size,
+            Reverse((cube.min.x(), cube.min.y(), cube.min.z())),
+        ));
+    }
+}
+
+impl<'a> Iterator for Optima<'a> {
+    type Item = Optimum;
+
+    fn next(&mut self) -> Option<Optimum> {
+        while let Some((in_range, Reverse(distance), size, Reverse((x, y, z)))) = self.queue.pop() {
+            if self.count.is_some_and(|count| in_range < count) {
+                self.queue.clear();
+                return None;
+            }
+
+            let min = Coord::new(x, y, z);
+            if size == 1 {
+                self.count = Some(in_range);
+                let bots = self
+                    .bots
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, b)| b.in_range(&min))
+                    .map(|(i, _)| i)
+                    .collect();
+
+                return Some(Optimum {
+                    position: min,
+                    distance,
+                    bots,
+                });
+            }
+
+            for cube in (Cube { min, size }).split() {
+                self.push(&cube);
+            }
+        }
+
+        None
+    }
+}
+
+// Every point in range of the most bots, nearest to `reference` first.
+pub fn optima(bots: &[Nanobot], reference: Coord) -> Optima<'_> {
+    let mut optima = Optima {
+        bots,
+        reference,
+        queue: BinaryHeap::new(),
+        count: None,
+    };
+    optima.push(&bounding_cube(bots));
+
+    optima
+}
+
+pub fn best_position(bots: &[Nanobot]) -> Coord {
+    optima(bots, Coord::new(0, 0, 0))
+        .next()
+        .expect("the search always reaches a single point")
+        .position
 }
 
 #[aoc(day23, part2)]
-fn answer_2(bots: &[Nanobot]) -> u64 {
-    let multiplier = 2;
-    let origin = Coord::new(0, 0, 0);
-    let (mut min, mut max) = find_bounds(bots.iter().map(|n| &n.pos));
-    let mut range = 1;
+fn answer_2(bots: &[Nanobot]) -> u128 {
+    optima(bots, Coord::new(0, 0, 0))
+        .next()
+        .expect("the search always reaches a single point")
+        .distance
+}
 
-    while range < max.x() - min.x() {
-        range *= multiplier;
+// A set of bot indices packed 64 to a word, so that intersections and
+// counts work a word at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BitSet(Vec<u64>);
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        BitSet(vec![0; len.div_ceil(64)])
     }
 
-    loop {
-        let mut max_neighbours = 0;
-        let mut best = origin.clone();
+    fn full(len: usize) -> Self {
+        let mut set = BitSet::new(len);
+        for i in 0..len {
+            set.insert(i);
+        }
 
-        for x in (min.x()..=max.x()).step_by(range as usize) {
-            for y in (min.y()..=max.y()).step_by(range as usize) {
-                for z in (min.z()..=max.z()).step_by(range as usize) {
-                    let c = Coord::new(x, y, z);
-                    let neighbours = bots
-                        .iter()
-                        .filter(|b| {
-                            (b.pos.manhattan_distance(&c) as i64 - b.radius as i64) / range <= 0
-                        })
-                        .count();
-                    if neighbours > max_neighbours {
-                        max_neighbours = neighbours;
-                        best = c
-                    } else if neighbours == max_neighbours {
-                        if origin.manhattan_distance(&c) < origin.manhattan_distance(&best) {
-                            best = c
-                        }
-                    }
+        set
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn remove(&mut self, i: usize) {
+        self.0[i / 64] &= !(1 << (i % 64));
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|&w| w == 0)
+    }
+
+    fn intersection(&self, other: &BitSet) -> BitSet {
+        BitSet(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(a, b)| a & b)
+                .collect(),
+        )
+    }
+
+    fn intersection_len(&self, other: &BitSet) -> usize {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
+    fn difference(&self, other: &BitSet) -> BitSet {
+        BitSet(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(a, b)| a & !b)
+                .collect(),
+        )
+    }
+
+    // Members in ascending order.
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+// Bots joined when their ranges overlap.
+pub struct OverlapGraph {
+    neighbours: Vec<BitSet>,
+}
+
+impl OverlapGraph {
+    pub fn new(bots: &[Nanobot]) -> Self {
+        let mut neighbours = vec![BitSet::new(bots.len()); bots.len()];
+        for i in 0..bots.len() {
+            for j in i + 1..bots.len() {
+                if bots[i].overlaps(&bots[j]) {
+                    neighbours[i].insert(j);
+                    neighbours[j].insert(i);
                 }
             }
         }
 
-        if range == 1 {
-            return origin.manhattan_distance(&best);
+        OverlapGraph { neighbours }
+    }
+
+    // The bots overlapping bot `bot`, in index order.
+    pub fn neighbours(&self, bot: usize) -> Vec<usize> {
+        self.neighbours[bot].iter().collect()
+    }
+
+    // Bron-Kerbosch with pivoting, keeping the largest clique found.
+    pub fn maximum_clique(&self) -> Vec<usize> {
+        let n = self.neighbours.len();
+        let mut best = Vec::new();
+        self.expand(&mut Vec::new(), BitSet::full(n), BitSet::new(n), &mut best);
+
+        best.sort();
+        best
+    }
+
+    fn expand(
+        &self,
+        clique: &mut Vec<usize>,
+        mut candidates: BitSet,
+        mut excluded: BitSet,
+        best: &mut Vec<usize>,
+    ) {
+        if clique.len() + candidates.len() <= best.len() {
+            return;
+        }
+        if candidates.is_empty() && excluded.is_empty() {
+            *best = clique.clone();
+            return;
+        }
+
+        let pivot = candidates
+            .iter()
+            .chain(excluded.iter())
+            .max_by_key(|&u| self.neighbours[u].intersection_len(&candidates))
+            .unwrap();
+
+        let branches: Vec<usize> = candidates
+            .difference(&self.neighbours[pivot])
+            .iter()
+            .collect();
+        for v in branches {
+            clique.push(v);
+            self.expand(
+                clique,
+                candidates.intersection(&self.neighbours[v]),
+                excluded.intersection(&self.neighbours[v]),
+                best,
+            );
+            clique.pop();
+
+            candidates.remove(v);
+            excluded.insert(v);
         }
+    }
+}
 
-        min = Coord::new(best.x() - range, best.y() - range, best.z() - range);
-        max = Coord::new(best.x() + range, best.y() + range, best.z() + range);
-        range /= multiplier;
+// Pairwise overlapping ranges need not share a point, so check the clique's
+// common intersection too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clique {
+    pub bots: Vec<usize>,
+    pub common_point: Option<Coord>,
+}
+
+pub fn largest_clique(bots: &[Nanobot]) -> Clique {
+    let members = OverlapGraph::new(bots).maximum_clique();
+    let common_point = common_point(members.iter().map(|&i| &bots[i]));
+
+    Clique {
+        bots: members,
+        common_point,
     }
 }
 
+// A range is the intersection of four slabs, one for each of x+y+z, x+y-z,
+// x-y+z and -x+y+z, the last three of which add up to the first. A point in
+// every range is then three values with the same parity, one from each of
+// the last three slabs, whose sum lies in the first. The sums are taken in
+// `i128`, and the point found is moved into the coordinates a `Coord` can
+// hold, which keeps it in every range as all the bots are there too.
+pub fn common_point<'a, I>(bots: I) -> Option<Coord>
+where
+    I: IntoIterator<Item = &'a Nanobot>,
+{
+    let slabs = bots
+        .into_iter()
+        .map(|b| {
+            let (x, y, z) = (
+                i128::from(b.pos.x()),
+                i128::from(b.pos.y()),
+                i128::from(b.pos.z()),
+            );
+            let r = i128::from(b.radius);
+            [x + y + z, x + y - z, x - y + z, -x + y + z].map(|c| (c - r, c + r))
+        })
+        .reduce(|a, b| [0, 1, 2, 3].map(|k| (a[k].0.max(b[k].0), a[k].1.min(b[k].1))));
+
+    let [sum, v, w, t] = match slabs {
+        Some(slabs) => slabs,
+        None => return Some(Coord::default()),
+    };
+
+    // The first value of `lo..=hi` with the given parity, if any.
+    let first = |(lo, hi): (i128, i128), parity: i128| {
+        Some(lo + (lo - parity).rem_euclid(2)).filter(|&lo| lo <= hi)
+    };
+
+    for parity in 0..2 {
+        let last = |(_, hi): (i128, i128)| hi - (hi - parity).rem_euclid(2);
+        let (v_lo, w_lo, t_lo) = match (first(v, parity), first(w, parity), first(t, parity)) {
+            (Some(v_lo), Some(w_lo), Some(t_lo)) => (v_lo, w_lo, t_lo),
+            _ => continue,
+        };
+
+        let least = v_lo + w_lo + t_lo;
+        let most = last(v) + last(w) + last(t);
+        let target = match first((sum.0.max(least), sum.1.min(most)), parity) {
+            Some(target) => target,
+            None => continue,
+        };
+
+        // Raise each value in turn until the sum reaches the target.
+        let mut extra = target - least;
+        let mut raise = |lo: i128, hi: i128| {
+            let step = extra.min(hi - lo);
+            extra -= step;
+            lo + step
+        };
+        let v = raise(v_lo, last(v));
+        let w = raise(w_lo, last(w));
+        let t = raise(t_lo, last(t));
+
+        let clamp = |c: i128| c.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64;
+        return Some(Coord::new(
+            clamp((v + w) / 2),
+            clamp((v + t) / 2),
+            clamp((w + t) / 2),
+        ));
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use manhattan::Manhattan;
 
     #[test]
     fn test_nanobot_parser() {
@@ -120,12 +780,91 @@ mod test {
         );
     }
 
+    #[test]
+    fn parser_accepts_whitespace_and_signs() {
+        assert_eq!(
+            Ok(Nanobot {
+                pos: Coord::new(-9223372036854775808, 2, 3),
+                radius: 18446744073709551615,
+            }),
+            " pos = < -9223372036854775808 , +2,3 >,  r= +18446744073709551615 ".parse()
+        );
+    }
+
+    #[test]
+    fn bots_at_the_limits() {
+        let (min, max, r) = (i64::MIN, i64::MAX, u64::MAX);
+        let bot = |x, y, z, radius| Nanobot {
+            pos: Coord::new(x, y, z),
+            radius,
+        };
+        let bots = vec![
+            bot(min, 0, 0, r),
+            bot(max, 0, 0, r),
+            bot(0, max, min, 0),
+            bot(max, max, max, 0),
+            bot(max, max, max, 0),
+        ];
+        let corner = Coord::new(max, max, max);
+
+        let field = NanobotField::new(&bots);
+        assert_eq!(vec![0, 1], field.covering(&Coord::new(0, 0, 0)));
+        assert_eq!(vec![1, 3, 4], field.covering(&corner));
+        assert_eq!(vec![0, 1], field.in_range_of(0));
+        assert_eq!(4, answer_1(&bots));
+
+        // Further from the origin than a `u64` can count.
+        assert_eq!(corner, best_position(&bots));
+        assert_eq!(3 * max as u128, answer_2(&bots));
+
+        assert_eq!(
+            Clique {
+                bots: vec![1, 3, 4],
+                common_point: Some(corner),
+            },
+            largest_clique(&bots)
+        );
+        let point = common_point(&bots[..2]).unwrap();
+        assert!(bots[..2].iter().all(|b| b.in_range(&point)));
+    }
+
+    #[test]
+    fn parser_reports_every_bad_line() {
+        let error = input_generator(
+            "\
+pos=<1,2,3>, r=4
+pos=<1,2>, r=4
+
+pos=<1,2,3>, r=-4
+pos=<99999999999999999999,2,3>, r=4
+pos=<1,2,3>, r=4
+",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            vec![
+                (2, LineError::Format("pos=<1,2>, r=4".to_string())),
+                (4, LineError::Format("pos=<1,2,3>, r=-4".to_string())),
+                (5, LineError::Number("99999999999999999999".to_string())),
+            ],
+            error.lines
+        );
+        assert_eq!(
+            "3 malformed line(s)\n  line 2: unrecognised nanobot \"pos=<1,2>, r=4\"\n  \
+             line 4: unrecognised nanobot \"pos=<1,2,3>, r=-4\"\n  \
+             line 5: number out of range: 99999999999999999999",
+            error.to_string()
+        );
+    }
+
     #[test]
     fn examples_1() {
         assert_eq!(
             7,
-            answer_1(&input_generator(
-                "\
+            answer_1(
+                &input_generator(
+                    "\
 pos=<0,0,0>, r=4
 pos=<1,0,0>, r=1
 pos=<4,0,0>, r=3
@@ -135,23 +874,229 @@ pos=<0,0,3>, r=1
 pos=<1,1,1>, r=1
 pos=<1,1,2>, r=1
 pos=<1,3,1>, r=1"
-            ))
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    // Bots spread around the origin, from a fixed seed.
+    fn random_bots(seed: u64, count: usize, spread: i64, radius: i64) -> Vec<Nanobot> {
+        let mut seed = seed;
+        let mut random = |n: i64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as i64 % n
+        };
+
+        (0..count)
+            .map(|_| Nanobot {
+                pos: Coord::new(
+                    random(2 * spread + 1) - spread,
+                    random(2 * spread + 1) - spread,
+                    random(2 * spread + 1) - spread,
+                ),
+                radius: 1 + random(radius) as u64,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_exhaustive_search() {
+        for seed in 0..10 {
+            let bots = random_bots(seed, 8, 10, 8);
+
+            let origin = Coord::new(0, 0, 0);
+            let mut best = (0, Reverse(0), Reverse((0, 0, 0)));
+            for x in -20..=20 {
+                for y in -20..=20 {
+                    for z in -20..=20 {
+                        let c = Coord::new(x, y, z);
+                        let n = bots.iter().filter(|b| b.in_range(&c)).count();
+                        best = best.max((
+                            n,
+                            Reverse(origin.manhattan_distance(&c)),
+                            Reverse((x, y, z)),
+                        ));
+                    }
+                }
+            }
+
+            let Reverse((x, y, z)) = best.2;
+            assert_eq!(Coord::new(x, y, z), best_position(&bots));
+        }
+    }
+
+    #[test]
+    fn field_matches_linear_scans() {
+        let bots = random_bots(1, 200, 1000, 1500);
+        let field = NanobotField::new(&bots);
+        let scan = |keep: &dyn Fn(&Nanobot) -> bool| -> Vec<usize> {
+            (0..bots.len()).filter(|&i| keep(&bots[i])).collect()
+        };
+
+        let points: Vec<Coord> = random_bots(2, 50, 1200, 1)
+            .into_iter()
+            .map(|b| b.pos)
+            .collect();
+        for c in points.iter() {
+            assert_eq!(scan(&|b| b.in_range(c)), field.covering(c));
+        }
+        assert_eq!(
+            points
+                .iter()
+                .map(|c| scan(&|b| b.in_range(c)).len())
+                .collect::<Vec<_>>(),
+            field.count_covering_all(&points)
+        );
+
+        for (i, bot) in bots.iter().enumerate() {
+            assert_eq!(scan(&|b| bot.in_range(&b.pos)), field.in_range_of(i));
+        }
+
+        let bounds = Bounds {
+            min: Coord::new(-20, -10, 0),
+            max: Coord::new(20, 10, 5),
+        };
+        let covering = field.covering_bounds(&bounds);
+        assert!(!covering.is_empty());
+        assert_eq!(
+            scan(&|b| Coord::region(&bounds.min, &bounds.max).all(|c| b.in_range(&c))),
+            covering
+        );
+    }
+
+    #[test]
+    fn ties_are_broken_by_distance_then_coordinate() {
+        let bots = input_generator(
+            "\
+pos=<10,0,0>, r=1
+pos=<-10,0,0>, r=1
+pos=<0,10,0>, r=1",
+        )
+        .unwrap();
+
+        assert_eq!(Coord::new(-9, 0, 0), best_position(&bots));
+    }
+
+    #[test]
+    fn lists_tied_optima_with_bots_in_range() {
+        let bots = input_generator(
+            "\
+pos=<10,0,0>, r=1
+pos=<12,0,0>, r=1
+pos=<-10,0,0>, r=1
+pos=<-8,0,0>, r=1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Optimum {
+                    position: Coord::new(-9, 0, 0),
+                    distance: 9,
+                    bots: vec![2, 3],
+                },
+                Optimum {
+                    position: Coord::new(11, 0, 0),
+                    distance: 11,
+                    bots: vec![0, 1],
+                },
+            ],
+            optima(&bots, Coord::new(0, 0, 0)).collect::<Vec<_>>()
         );
+
+        let nearest = optima(&bots, Coord::new(20, 0, 0)).next().unwrap();
+        assert_eq!(Coord::new(11, 0, 0), nearest.position);
+        assert_eq!(9, nearest.distance);
     }
 
     #[test]
     fn examples_2() {
         assert_eq!(
             36,
-            answer_2(&input_generator(
-                "\
+            answer_2(
+                &input_generator(
+                    "\
 pos=<10,12,12>, r=2
 pos=<12,14,12>, r=2
 pos=<16,12,12>, r=4
 pos=<14,14,14>, r=6
 pos=<50,50,50>, r=200
 pos=<10,10,10>, r=5"
-            ))
+                )
+                .unwrap()
+            )
         );
     }
+
+    #[test]
+    fn bit_sets() {
+        let mut a = BitSet::new(130);
+        for &i in [0, 63, 64, 129].iter() {
+            a.insert(i);
+        }
+        let mut b = BitSet::full(130);
+        b.remove(64);
+
+        assert_eq!(vec![0, 63, 64, 129], a.iter().collect::<Vec<_>>());
+        assert_eq!(129, b.len());
+        assert_eq!(3, a.intersection_len(&b));
+        assert_eq!(vec![64], a.difference(&b).iter().collect::<Vec<_>>());
+        assert!(a.intersection(&BitSet::new(130)).is_empty());
+    }
+
+    #[test]
+    fn overlap_graph() {
+        let bots = input_generator(
+            "\
+pos=<0,0,0>, r=1
+pos=<3,0,0>, r=2
+pos=<10,0,0>, r=1",
+        )
+        .unwrap();
+        let graph = OverlapGraph::new(&bots);
+
+        assert_eq!(vec![1], graph.neighbours(0));
+        assert!(graph.neighbours(2).is_empty());
+        assert_eq!(vec![0, 1], graph.maximum_clique());
+    }
+
+    #[test]
+    fn clique_with_common_point() {
+        let bots = input_generator(
+            "\
+pos=<10,12,12>, r=2
+pos=<12,14,12>, r=2
+pos=<16,12,12>, r=4
+pos=<14,14,14>, r=6
+pos=<50,50,50>, r=200
+pos=<10,10,10>, r=5",
+        )
+        .unwrap();
+        let clique = largest_clique(&bots);
+
+        assert_eq!(vec![0, 1, 2, 3, 4], clique.bots);
+        let point = clique.common_point.unwrap();
+        assert!(clique.bots.iter().all(|&i| bots[i].in_range(&point)));
+    }
+
+    #[test]
+    fn clique_without_common_point() {
+        let bots = input_generator(
+            "\
+pos=<1,1,1>, r=2
+pos=<1,-1,-1>, r=2
+pos=<-1,1,-1>, r=2
+pos=<-1,-1,1>, r=2
+pos=<20,0,0>, r=2",
+        )
+        .unwrap();
+        let clique = largest_clique(&bots);
+
+        assert_eq!(vec![0, 1, 2, 3], clique.bots);
+        assert_eq!(None, clique.common_point);
+        assert!(common_point(&bots[..3]).is_some());
+    }
 }
@@ -11,15 +11,26 @@ extern crate aoc_runner_derive;
 extern crate failure;
 
 pub mod coord;
+pub mod manhattan;
 pub mod point;
+pub mod ring;
+pub mod scheduler;
+pub mod summed_area;
 
 pub mod day01;
 pub mod day02;
 pub mod day03;
+pub mod day04;
+pub mod day05;
 pub mod day06;
 pub mod day07;
 pub mod day08;
 pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
 pub mod day23;
 
 aoc_lib! { year = 2018 }
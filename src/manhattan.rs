@@ -0,0 +1,97 @@
+use coord::Coord;
+use point::Point;
+use std::hash::Hash;
+
+pub trait Manhattan: Clone + Eq + Hash + Default {
+    fn manhattan_distance(&self, b: &Self) -> u128;
+
+    fn min(&self, b: &Self) -> Self;
+
+    fn max(&self, b: &Self) -> Self;
+
+    // Every lattice point within the inclusive box spanned by `min` and `max`,
+    // generated as it is needed.
+    fn region(min: &Self, max: &Self) -> impl Iterator<Item = Self>;
+
+    fn on_boundary(&self, min: &Self, max: &Self) -> bool;
+}
+
+impl Manhattan for Point {
+    fn manhattan_distance(&self, b: &Self) -> u128 {
+        u128::from(Point::manhattan_distance(self, b))
+    }
+
+    fn min(&self, b: &Self) -> Self {
+        Point::min(self, b)
+    }
+
+    fn max(&self, b: &Self) -> Self {
+        Point::max(self, b)
+    }
+
+    fn region(min: &Self, max: &Self) -> impl Iterator<Item = Self> {
+        let ys = min.y()..=max.y();
+        (min.x()..=max.x()).flat_map(move |x| ys.clone().map(move |y| Point::new(x, y)))
+    }
+
+    fn on_boundary(&self, min: &Self, max: &Self) -> bool {
+        self.x() == min.x() || self.x() == max.x() || self.y() == min.y() || self.y() == max.y()
+    }
+}
+
+impl Manhattan for Coord {
+    fn manhattan_distance(&self, b: &Self) -> u128 {
+        Coord::manhattan_distance(self, b)
+    }
+
+    fn min(&self, b: &Self) -> Self {
+        Coord::min(self, b)
+    }
+
+    fn max(&self, b: &Self) -> Self {
+        Coord::max(self, b)
+    }
+
+    fn region(min: &Self, max: &Self) -> impl Iterator<Item = Self> {
+        let (ys, zs) = (min.y()..=max.y(), min.z()..=max.z());
+        (min.x()..=max.x()).flat_map(move |x| {
+            let zs = zs.clone();
+            ys.clone()
+                .flat_map(move |y| zs.clone().map(move |z| Coord::new(x, y, z)))
+        })
+    }
+
+    fn on_boundary(&self, min: &Self, max: &Self) -> bool {
+        self.x() == min.x()
+            || self.x() == max.x()
+            || self.y() == min.y()
+            || self.y() == max.y()
+            || self.z() == min.z()
+            || self.z() == max.z()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn region_is_inclusive() {
+        assert_eq!(
+            6,
+            Point::region(&Point::new(0, 0), &Point::new(1, 2)).count()
+        );
+        assert_eq!(
+            24,
+            Coord::region(&Coord::new(-1, 0, 0), &Coord::new(0, 2, 3)).count()
+        );
+    }
+
+    #[test]
+    fn on_boundary() {
+        let (min, max) = (Coord::new(0, 0, 0), Coord::new(2, 2, 2));
+        assert!(Coord::new(1, 1, 0).on_boundary(&min, &max));
+        assert!(Coord::new(2, 1, 1).on_boundary(&min, &max));
+        assert!(!Coord::new(1, 1, 1).on_boundary(&min, &max));
+    }
+}
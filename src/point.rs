@@ -1,4 +1,6 @@
-#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+use std::cmp::{max, min};
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Default)]
 pub struct Point(i64, i64);
 
 impl Point {
@@ -20,6 +22,14 @@ impl Point {
     pub fn y(&self) -> i64 {
         self.1
     }
+
+    pub fn min(&self, b: &Self) -> Self {
+        Point::new(min(self.x(), b.x()), min(self.y(), b.y()))
+    }
+
+    pub fn max(&self, b: &Self) -> Self {
+        Point::new(max(self.x(), b.x()), max(self.y(), b.y()))
+    }
 }
 
 #[cfg(test)]
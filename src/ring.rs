@@ -0,0 +1,200 @@
+// A circular list with a cursor, backed by index links so that inserting and
+// removing at the cursor is O(1).
+#[derive(Debug, Clone)]
+pub struct Ring<T> {
+    nodes: Vec<Link<T>>,
+    free: Vec<usize>,
+    current: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Link<T> {
+    value: Option<T>,
+    prev: usize,
+    next: usize,
+}
+
+impl<T> Ring<T> {
+    pub fn new(first: T) -> Self {
+        Ring::with_capacity(first, 1)
+    }
+
+    pub fn with_capacity(first: T, capacity: usize) -> Self {
+        let mut nodes = Vec::with_capacity(capacity);
+        nodes.push(Link {
+            value: Some(first),
+            prev: 0,
+            next: 0,
+        });
+
+        Ring {
+            nodes,
+            free: Vec::new(),
+            current: 0,
+            len: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn current(&self) -> Option<&T> {
+        self.nodes.get(self.current).and_then(|n| n.value.as_ref())
+    }
+
+    // Moves the cursor clockwise by `n`, or counter-clockwise if negative,
+    // taking whichever way round is shorter.
+    pub fn rotate(&mut self, n: isize) {
+        if self.len == 0 {
+            return;
+        }
+
+        let len = self.len as isize;
+        let steps = ((n % len) + len) % len;
+        if steps <= len / 2 {
+            for _ in 0..steps {
+                self.current = self.nodes[self.current].next;
+            }
+        } else {
+            for _ in 0..len - steps {
+                self.current = self.nodes[self.current].prev;
+            }
+        }
+    }
+
+    // Inserts `value` clockwise of the cursor and moves the cursor onto it.
+    pub fn insert(&mut self, value: T) {
+        if self.len == 0 {
+            *self = Ring::new(value);
+            return;
+        }
+
+        let prev = self.current;
+        let next = self.nodes[prev].next;
+        let link = Link {
+            value: Some(value),
+            prev,
+            next,
+        };
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = link;
+                i
+            }
+            None => {
+                self.nodes.push(link);
+                self.nodes.len() - 1
+            }
+        };
+
+        self.nodes[prev].next = i;
+        self.nodes[next].prev = i;
+        self.current = i;
+        self.len += 1;
+    }
+
+    // Removes the value at the cursor, moving the cursor clockwise.
+    pub fn remove(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let i = self.current;
+        let (prev, next) = (self.nodes[i].prev, self.nodes[i].next);
+        self.nodes[prev].next = next;
+        self.nodes[next].prev = prev;
+        self.current = next;
+        self.len -= 1;
+        self.free.push(i);
+
+        self.nodes[i].value.take()
+    }
+
+    // Values clockwise from the cursor.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            ring: self,
+            at: self.current,
+            remaining: self.len,
+        }
+    }
+}
+
+pub struct Iter<'a, T: 'a> {
+    ring: &'a Ring<T>,
+    at: usize,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let link = &self.ring.nodes[self.at];
+        self.at = link.next;
+        self.remaining -= 1;
+
+        link.value.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn values(ring: &Ring<i32>) -> Vec<i32> {
+        ring.iter().cloned().collect()
+    }
+
+    #[test]
+    fn rotates_both_ways() {
+        let mut ring = Ring::new(0);
+        for i in 1..5 {
+            ring.insert(i);
+        }
+        assert_eq!(vec![4, 0, 1, 2, 3], values(&ring));
+
+        ring.rotate(2);
+        assert_eq!(Some(&1), ring.current());
+        ring.rotate(-3);
+        assert_eq!(Some(&3), ring.current());
+        ring.rotate(13);
+        assert_eq!(Some(&1), ring.current());
+        ring.rotate(-11);
+        assert_eq!(Some(&0), ring.current());
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut ring = Ring::new(0);
+        ring.insert(1);
+        ring.insert(2);
+        ring.rotate(-1);
+
+        assert_eq!(Some(1), ring.remove());
+        assert_eq!(vec![2, 0], values(&ring));
+
+        ring.insert(3);
+        assert_eq!(vec![3, 0, 2], values(&ring));
+        assert_eq!(3, ring.len());
+
+        assert_eq!(Some(3), ring.remove());
+        assert_eq!(Some(0), ring.remove());
+        assert_eq!(Some(2), ring.remove());
+        assert_eq!(None, ring.remove());
+        assert!(ring.is_empty());
+
+        ring.insert(4);
+        assert_eq!(vec![4], values(&ring));
+    }
+}
@@ -0,0 +1,537 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task<T> {
+    pub worker: usize,
+    pub step: T,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Idle {
+    pub worker: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule<T> {
+    pub workers: usize,
+    pub tasks: Vec<Task<T>>,
+    pub idle: Vec<Idle>,
+    pub total_time: usize,
+}
+
+impl<T: Display> Schedule<T> {
+    // Renders the schedule second by second, listing what each worker is
+    // doing and which steps are done so far.
+    pub fn gantt(&self) -> String {
+        let mut done: Vec<&Task<T>> = self.tasks.iter().collect();
+        done.sort_by_key(|t| (t.end, t.worker));
+
+        let mut out = String::from("Second");
+        for w in 1..=self.workers {
+            out.push_str(&format!("   Worker {}", w));
+        }
+        out.push_str("   Done\n");
+
+        for second in 0..=self.total_time {
+            let mut row = format!("{:>4}  ", second);
+            for w in 0..self.workers {
+                let cell = self
+                    .tasks
+                    .iter()
+                    .find(|t| t.worker == w && t.start <= second && second < t.end)
+                    .map_or(".".to_string(), |t| t.step.to_string());
+                row.push_str(&format!("{:>7}    ", cell));
+            }
+            row.push_str("   ");
+            for t in done.iter().take_while(|t| t.end <= second) {
+                row.push_str(&t.step.to_string());
+            }
+
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timing {
+    pub duration: usize,
+    pub earliest_start: usize,
+    pub latest_start: usize,
+}
+
+impl Timing {
+    pub fn earliest_finish(&self) -> usize {
+        self.earliest_start + self.duration
+    }
+
+    pub fn latest_finish(&self) -> usize {
+        self.latest_start + self.duration
+    }
+
+    pub fn slack(&self) -> usize {
+        self.latest_start - self.earliest_start
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis<T: Hash + Eq> {
+    pub timings: HashMap<T, Timing>,
+    pub critical_path: Vec<T>,
+    // Completion time with as many workers as there are steps
+    pub lower_bound: usize,
+    // Completion time with the scheduler's workers
+    pub total_time: usize,
+}
+
+// The steps that could never start because they are part of a cycle or wait
+// on one, in ascending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unschedulable<T>(pub Vec<T>);
+
+impl<T: Debug> Display for Unschedulable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "steps blocked by a cycle: {:?}", self.0)
+    }
+}
+
+impl<T: Debug> std::error::Error for Unschedulable<T> {}
+
+// Schedules a dependency graph, given as a map of each step to its
+// prerequisites, over a fixed number of workers.
+//
+// Ties are broken deterministically: whenever workers become free, the ready
+// steps are handed out in ascending order, lowest numbered worker first.
+// Prerequisites that aren't steps themselves are taken as already done.
+pub struct Scheduler<F> {
+    workers: usize,
+    duration: F,
+}
+
+impl<F> Scheduler<F> {
+    pub fn new(workers: usize, duration: F) -> Self {
+        assert!(workers > 0, "at least one worker is required");
+
+        Scheduler { workers, duration }
+    }
+
+    pub fn run<T>(&self, edges: &HashMap<T, Vec<T>>) -> Result<Schedule<T>, Unschedulable<T>>
+    where
+        T: Ord + Hash + Clone,
+        F: Fn(&T) -> usize,
+    {
+        let successors = successors(edges);
+        let mut indegree = indegree(edges);
+        let mut ready: BTreeSet<&T> = indegree
+            .iter()
+            .filter(|(_, &n)| n == 0)
+            .map(|(&k, _)| k)
+            .collect();
+        let mut free: BTreeSet<usize> = (0..self.workers).collect();
+        let mut running: Vec<Option<Task<T>>> = (0..self.workers).map(|_| None).collect();
+        let mut completions: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::new();
+        let mut tasks = Vec::with_capacity(edges.len());
+        let mut time = 0;
+
+        loop {
+            while let Some(&Reverse((end, worker))) = completions.peek() {
+                if end > time {
+                    break;
+                }
+                completions.pop();
+
+                let task = running[worker].take().unwrap();
+                for &s in successors.get(&task.step).into_iter().flatten() {
+                    let n = indegree.get_mut(s).unwrap();
+                    *n -= 1;
+                    if *n == 0 {
+                        ready.insert(s);
+                    }
+                }
+                free.insert(worker);
+                tasks.push(task);
+            }
+
+            while !free.is_empty() && !ready.is_empty() {
+                let worker = *free.iter().next().unwrap();
+                let step = *ready.iter().next().unwrap();
+                free.remove(&worker);
+                ready.remove(step);
+
+                let end = time + (self.duration)(step);
+                completions.push(Reverse((end, worker)));
+                running[worker] = Some(Task {
+                    worker,
+                    step: step.clone(),
+                    start: time,
+                    end,
+                });
+            }
+
+            match completions.peek() {
+                Some(&Reverse((end, _))) => time = end,
+                None => break,
+            }
+        }
+
+        if tasks.len() < edges.len() {
+            let mut blocked: Vec<T> = indegree
+                .into_iter()
+                .filter(|&(_, n)| n > 0)
+                .map(|(k, _)| k.clone())
+                .collect();
+            blocked.sort();
+            return Err(Unschedulable(blocked));
+        }
+
+        tasks.sort_by_key(|t| (t.start, t.worker));
+        let idle = idle_periods(&tasks, self.workers, time);
+
+        Ok(Schedule {
+            workers: self.workers,
+            tasks,
+            idle,
+            total_time: time,
+        })
+    }
+
+    pub fn analyse<T>(&self, edges: &HashMap<T, Vec<T>>) -> Result<Analysis<T>, Unschedulable<T>>
+    where
+        T: Ord + Hash + Clone,
+        F: Fn(&T) -> usize,
+    {
+        let total_time = self.run(edges)?.total_time;
+        let (timings, lower_bound) = timings(edges, &self.duration);
+        let critical_path = critical_path(edges, &timings, lower_bound);
+
+        Ok(Analysis {
+            timings,
+            critical_path,
+            lower_bound,
+            total_time,
+        })
+    }
+}
+
+// Steps in dependency order, smallest first among those ready at the same
+// point. Steps that are part of a cycle are never ready and are left out.
+fn topological_order<T: Ord + Hash + Clone>(edges: &HashMap<T, Vec<T>>) -> Vec<T> {
+    let successors = successors(edges);
+    let mut indegree = indegree(edges);
+    let mut order = Vec::with_capacity(edges.len());
+    let mut ready: BTreeSet<&T> = indegree
+        .iter()
+        .filter(|(_, &n)| n == 0)
+        .map(|(&k, _)| k)
+        .collect();
+
+    while let Some(step) = ready.iter().next().cloned() {
+        ready.remove(step);
+        order.push(step.clone());
+
+        for &s in successors.get(step).into_iter().flatten() {
+            let n = indegree.get_mut(s).unwrap();
+            *n -= 1;
+            if *n == 0 {
+                ready.insert(s);
+            }
+        }
+    }
+
+    order
+}
+
+// Inverts the prerequisite map so that each step lists the steps waiting on
+// it. Prerequisites that aren't steps themselves are ignored.
+fn successors<T: Hash + Eq>(edges: &HashMap<T, Vec<T>>) -> HashMap<&T, Vec<&T>> {
+    let mut successors: HashMap<&T, Vec<&T>> = HashMap::with_capacity(edges.len());
+    for (k, v) in edges.iter() {
+        for p in v.iter().filter(|p| edges.contains_key(p)) {
+            successors.entry(p).or_default().push(k);
+        }
+    }
+
+    successors
+}
+
+// How many of each step's prerequisites are steps themselves, matching the
+// edges `successors` keeps.
+fn indegree<T: Hash + Eq>(edges: &HashMap<T, Vec<T>>) -> HashMap<&T, usize> {
+    edges
+        .iter()
+        .map(|(k, v)| (k, v.iter().filter(|p| edges.contains_key(p)).count()))
+        .collect()
+}
+
+fn timings<T, F>(edges: &HashMap<T, Vec<T>>, duration: &F) -> (HashMap<T, Timing>, usize)
+where
+    T: Ord + Hash + Clone,
+    F: Fn(&T) -> usize,
+{
+    let order = topological_order(edges);
+    let mut timings: HashMap<T, Timing> = HashMap::with_capacity(order.len());
+
+    for step in order.iter() {
+        let earliest_start = edges[step]
+            .iter()
+            .filter_map(|p| timings.get(p))
+            .map(|t| t.earliest_finish())
+            .max()
+            .unwrap_or(0);
+
+        timings.insert(
+            step.clone(),
+            Timing {
+                duration: duration(step),
+                earliest_start,
+                latest_start: 0,
+            },
+        );
+    }
+
+    let lower_bound = timings
+        .values()
+        .map(|t| t.earliest_finish())
+        .max()
+        .unwrap_or(0);
+
+    let mut latest_finish: HashMap<&T, usize> = HashMap::with_capacity(order.len());
+    for step in order.iter().rev() {
+        let finish = *latest_finish.get(step).unwrap_or(&lower_bound);
+        let timing = timings.get_mut(step).unwrap();
+        timing.latest_start = finish - timing.duration;
+
+        for p in edges[step].iter().filter(|p| edges.contains_key(p)) {
+            let f = latest_finish.entry(p).or_insert(timing.latest_start);
+            if timing.latest_start < *f {
+                *f = timing.latest_start;
+            }
+        }
+    }
+
+    (timings, lower_bound)
+}
+
+// Walks back from the last step to finish through prerequisites without
+// slack that finish exactly when their dependent may start.
+fn critical_path<T: Ord + Hash + Clone>(
+    edges: &HashMap<T, Vec<T>>,
+    timings: &HashMap<T, Timing>,
+    lower_bound: usize,
+) -> Vec<T> {
+    let mut path = Vec::new();
+    let mut current = timings
+        .iter()
+        .filter(|(_, t)| t.slack() == 0 && t.earliest_finish() == lower_bound)
+        .map(|(k, _)| k)
+        .min();
+
+    while let Some(step) = current {
+        path.push(step.clone());
+
+        let start = timings[step].earliest_start;
+        current = edges[step]
+            .iter()
+            .filter(|p| {
+                timings
+                    .get(p)
+                    .is_some_and(|t| t.slack() == 0 && t.earliest_finish() == start)
+            })
+            .min();
+    }
+
+    path.reverse();
+    path
+}
+
+// Expects tasks sorted by start time.
+fn idle_periods<T>(tasks: &[Task<T>], workers: usize, total_time: usize) -> Vec<Idle> {
+    let mut idle = Vec::new();
+    let mut free_since = vec![0; workers];
+
+    for t in tasks.iter() {
+        if t.start > free_since[t.worker] {
+            idle.push(Idle {
+                worker: t.worker,
+                start: free_since[t.worker],
+                end: t.start,
+            });
+        }
+        free_since[t.worker] = t.end;
+    }
+
+    for (worker, &start) in free_since.iter().enumerate() {
+        if total_time > start {
+            idle.push(Idle {
+                worker,
+                start,
+                end: total_time,
+            });
+        }
+    }
+
+    idle.sort_by_key(|i| (i.worker, i.start));
+    idle
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> HashMap<&'static str, Vec<&'static str>> {
+        let mut edges = HashMap::new();
+        edges.insert("C", vec![]);
+        edges.insert("A", vec!["C"]);
+        edges.insert("F", vec!["C"]);
+        edges.insert("B", vec!["A"]);
+        edges.insert("D", vec!["A"]);
+        edges.insert("E", vec!["B", "D", "F"]);
+        edges
+    }
+
+    fn duration(step: &&str) -> usize {
+        (step.as_bytes()[0] - b'A') as usize + 1
+    }
+
+    fn task(worker: usize, step: &'static str, start: usize, end: usize) -> Task<&'static str> {
+        Task {
+            worker,
+            step,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn example_schedule() {
+        let schedule = Scheduler::new(2, duration).run(&example()).unwrap();
+
+        assert_eq!(15, schedule.total_time);
+        assert_eq!(
+            vec![
+                task(0, "C", 0, 3),
+                task(0, "A", 3, 4),
+                task(1, "F", 3, 9),
+                task(0, "B", 4, 6),
+                task(0, "D", 6, 10),
+                task(0, "E", 10, 15),
+            ],
+            schedule.tasks
+        );
+        assert_eq!(
+            vec![
+                Idle {
+                    worker: 1,
+                    start: 0,
+                    end: 3
+                },
+                Idle {
+                    worker: 1,
+                    start: 9,
+                    end: 15
+                },
+            ],
+            schedule.idle
+        );
+    }
+
+    #[test]
+    fn example_analysis() {
+        let analysis = Scheduler::new(1, duration).analyse(&example()).unwrap();
+
+        assert_eq!(vec!["C", "F", "E"], analysis.critical_path);
+        assert_eq!(14, analysis.lower_bound);
+        assert_eq!(21, analysis.total_time);
+
+        let slack: Vec<usize> = ["A", "B", "C", "D", "E", "F"]
+            .iter()
+            .map(|s| analysis.timings[s].slack())
+            .collect();
+        assert_eq!(vec![1, 3, 0, 1, 0, 0], slack);
+
+        let d = &analysis.timings["D"];
+        assert_eq!((4, 5), (d.earliest_start, d.latest_start));
+    }
+
+    #[test]
+    fn long_durations() {
+        let schedule = Scheduler::new(2, |s: &&str| duration(s) * 1_000_000_000_000)
+            .run(&example())
+            .unwrap();
+
+        assert_eq!(15_000_000_000_000, schedule.total_time);
+        assert_eq!(6, schedule.tasks.len());
+    }
+
+    #[test]
+    fn thousands_of_steps() {
+        // Chains of 1000 steps each, with every step also waiting on the
+        // matching step of the previous chain.
+        let mut edges: HashMap<u32, Vec<u32>> = HashMap::new();
+        for chain in 0..4 {
+            for i in 0..1000 {
+                let step = chain * 1000 + i;
+                let mut prerequisites = Vec::new();
+                if i > 0 {
+                    prerequisites.push(step - 1);
+                }
+                if chain > 0 {
+                    prerequisites.push(step - 1000);
+                }
+                edges.insert(step, prerequisites);
+            }
+        }
+
+        let schedule = Scheduler::new(4, |_: &u32| 3).run(&edges).unwrap();
+        assert_eq!(4000, schedule.tasks.len());
+        assert_eq!(3 * 1003, schedule.total_time);
+
+        let schedule = Scheduler::new(1, |_: &u32| 3).run(&edges).unwrap();
+        assert_eq!(3 * 4000, schedule.total_time);
+    }
+
+    #[test]
+    fn single_worker_runs_sequentially() {
+        let schedule = Scheduler::new(1, duration).run(&example()).unwrap();
+
+        assert_eq!(21, schedule.total_time);
+        assert!(schedule.idle.is_empty());
+    }
+
+    #[test]
+    fn outside_prerequisites_are_done() {
+        let mut edges = HashMap::new();
+        edges.insert("B", vec!["A"]);
+        edges.insert("C", vec!["A", "B"]);
+
+        let schedule = Scheduler::new(1, duration).run(&edges).unwrap();
+        assert_eq!(vec![task(0, "B", 0, 2), task(0, "C", 2, 5)], schedule.tasks);
+
+        let analysis = Scheduler::new(1, duration).analyse(&edges).unwrap();
+        assert_eq!(vec!["B", "C"], analysis.critical_path);
+        assert_eq!(5, analysis.lower_bound);
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let mut edges = example();
+        edges.insert("C", vec!["E"]);
+        edges.insert("G", vec![]);
+
+        let blocked = Unschedulable(vec!["A", "B", "C", "D", "E", "F"]);
+        assert_eq!(
+            Err(blocked.clone()),
+            Scheduler::new(2, duration).run(&edges)
+        );
+        assert_eq!(Err(blocked), Scheduler::new(2, duration).analyse(&edges));
+    }
+}
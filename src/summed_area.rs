@@ -0,0 +1,89 @@
+// A 2D prefix sum over a grid of values, answering the sum of any rectangle
+// in constant time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummedAreaTable {
+    width: usize,
+    height: usize,
+    // Sums of the rectangles from the origin, with a row and column of zeroes
+    // in front so that queries need no bounds checks.
+    sums: Vec<i64>,
+}
+
+impl SummedAreaTable {
+    pub fn new<F>(width: usize, height: usize, value: F) -> Self
+    where
+        F: Fn(usize, usize) -> i64,
+    {
+        let stride = width + 1;
+        let mut sums = vec![0; stride * (height + 1)];
+        for y in 0..height {
+            for x in 0..width {
+                sums[(y + 1) * stride + x + 1] =
+                    value(x, y) + sums[y * stride + x + 1] + sums[(y + 1) * stride + x]
+                        - sums[y * stride + x];
+            }
+        }
+
+        SummedAreaTable {
+            width,
+            height,
+            sums,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn at(&self, x: usize, y: usize) -> i64 {
+        self.sums[y * (self.width + 1) + x]
+    }
+
+    // The sum of the `width` by `height` rectangle with its top left corner at
+    // `x`, `y`.
+    pub fn sum(&self, x: usize, y: usize, width: usize, height: usize) -> i64 {
+        assert!(x + width <= self.width && y + height <= self.height);
+
+        self.at(x + width, y + height) - self.at(x, y + height) - self.at(x + width, y)
+            + self.at(x, y)
+    }
+
+    pub fn value(&self, x: usize, y: usize) -> i64 {
+        self.sum(x, y, 1, 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sums_match_brute_force() {
+        let value = |x: usize, y: usize| (x * 7 + y * 3) as i64 % 5 - 2;
+        let table = SummedAreaTable::new(6, 4, value);
+
+        for y in 0..4 {
+            for x in 0..6 {
+                assert_eq!(value(x, y), table.value(x, y));
+                for h in 0..=4 - y {
+                    for w in 0..=6 - x {
+                        let expected: i64 = (y..y + h)
+                            .flat_map(|y| (x..x + w).map(move |x| value(x, y)))
+                            .sum();
+                        assert_eq!(expected, table.sum(x, y, w, h));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let table = SummedAreaTable::new(0, 0, |_, _| 1);
+        assert_eq!(0, table.sum(0, 0, 0, 0));
+    }
+}